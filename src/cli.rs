@@ -0,0 +1,180 @@
+// Command line options for the game binary
+use gg::debug::DEFAULTDEBUG;
+
+pub const USAGE: &str = "\
+Usage: tree_game [OPTIONS]
+
+Options:
+  --window WIDTHxHEIGHT   Window size in pixels (default 1600x1024)
+  --layers N              Number of layers in the generated tree
+  --seed N                Generate a random tree from this seed
+  --setup FILE            Setup file to load (default setup.cfg if it exists)
+  --level FILE            Play a saved level instead of generating a tree
+  --theme NAME|FILE       Colour theme: classic, bark, okabe-ito, high-contrast or a theme file
+  --replay FILE           Play back a replay headlessly and check it still matches
+  --record FILE           Record the input to a replay file
+  --headless FRAMES       Run for FRAMES frames without a window
+  --export-svg FILE       Write the starting tree to an SVG file and exit
+  --snapshot FILE         Draw the game without a GPU to a PNG file, after any --headless frames
+  --golden FILE           Compare the --snapshot against this reference PNG
+  --tolerance N           Largest channel difference --golden ignores (default 2)
+  --svg-overlay LIST      Comma separated overlays for --export-svg: labels, connections,
+                          entities or none (default entities)
+  --debug-flags MASK      Debug flags to enable, in decimal or 0x hex
+  -h, --help              Print this help and exit
+
+Exit codes: 0 on success, 1 if a file could not be used, a replay diverged or a snapshot did not
+match its reference, 2 for invalid arguments.";
+
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub enum Command {
+    Run(Box<Options>),
+    Help
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SvgOverlays {
+    pub labels: bool,
+    pub connections: bool,
+    pub entities: bool
+}
+
+impl Default for SvgOverlays {
+    fn default() -> Self {
+        SvgOverlays {
+            labels: false,
+            connections: false,
+            entities: true
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub window_size: (u32, u32),
+    pub layers: Option<usize>,
+    pub seed: Option<u64>,
+    pub setup_file: Option<String>,
+    pub level_file: Option<String>,
+    pub theme: Option<String>,
+    pub replay_file: Option<String>,
+    pub record_file: Option<String>,
+    pub headless_frames: Option<usize>,
+    pub svg_file: Option<String>,
+    pub svg_overlays: SvgOverlays,
+    pub snapshot_file: Option<String>,
+    pub golden_file: Option<String>,
+    pub tolerance: u8,
+    pub debug_flags: u32
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            window_size: (1600, 1024),
+            layers: None,
+            seed: None,
+            setup_file: None,
+            level_file: None,
+            theme: None,
+            replay_file: None,
+            record_file: None,
+            headless_frames: None,
+            svg_file: None,
+            svg_overlays: SvgOverlays::default(),
+            snapshot_file: None,
+            golden_file: None,
+            tolerance: 2,
+            debug_flags: DEFAULTDEBUG
+        }
+    }
+}
+
+// Parses the arguments after the program name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--window" => options.window_size = parse_window_size(&value(arg)?)?,
+            "--fullscreen" => return Err("--fullscreen is not supported by the renderer, use --window to pick a size".to_string()),
+            "--layers" => {
+                let layers = parse_number(arg, &value(arg)?)?;
+                if layers == 0 {
+                    return Err("--layers must be at least 1".to_string());
+                }
+                options.layers = Some(layers);
+            },
+            "--seed" => options.seed = Some(parse_number(arg, &value(arg)?)?),
+            "--setup" => options.setup_file = Some(value(arg)?),
+            "--level" => options.level_file = Some(value(arg)?),
+            "--theme" => options.theme = Some(value(arg)?),
+            "--replay" => options.replay_file = Some(value(arg)?),
+            "--record" => options.record_file = Some(value(arg)?),
+            "--headless" => options.headless_frames = Some(parse_number(arg, &value(arg)?)?),
+            "--export-svg" => options.svg_file = Some(value(arg)?),
+            "--svg-overlay" => options.svg_overlays = parse_overlays(&value(arg)?)?,
+            "--snapshot" => options.snapshot_file = Some(value(arg)?),
+            "--golden" => options.golden_file = Some(value(arg)?),
+            "--tolerance" => options.tolerance = parse_number(arg, &value(arg)?)?,
+            "--debug-flags" => options.debug_flags = parse_flags(&value(arg)?)?,
+            _ => return Err(format!("Unknown argument '{}'", arg))
+        }
+    }
+
+    if options.replay_file.is_some() && options.record_file.is_some() {
+        return Err("--replay and --record cannot be used together".to_string());
+    }
+    if options.level_file.is_some() && (options.layers.is_some() || options.seed.is_some()) {
+        return Err("--layers and --seed have no effect with --level".to_string());
+    }
+    if options.golden_file.is_some() && options.snapshot_file.is_none() {
+        return Err("--golden needs a --snapshot to compare".to_string());
+    }
+    Ok(Command::Run(Box::new(options)))
+}
+
+fn parse_number<T: ::std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a whole number but got '{}'", name, value))
+}
+
+fn parse_window_size(value: &str) -> Result<(u32, u32), String> {
+    let parts: Vec<&str> = value.split('x').collect();
+    if parts.len() != 2 {
+        return Err(format!("--window expects WIDTHxHEIGHT but got '{}'", value));
+    }
+    let width = parse_number("--window", parts[0])?;
+    let height = parse_number("--window", parts[1])?;
+    if width == 0 || height == 0 {
+        return Err("--window sizes must be positive".to_string());
+    }
+    Ok((width, height))
+}
+
+fn parse_overlays(value: &str) -> Result<SvgOverlays, String> {
+    let mut overlays = SvgOverlays { labels: false, connections: false, entities: false };
+    for overlay in value.split(',') {
+        match overlay.trim() {
+            "labels" => overlays.labels = true,
+            "connections" => overlays.connections = true,
+            "entities" => overlays.entities = true,
+            "none" => {},
+            other => return Err(format!("Unknown SVG overlay '{}'", other))
+        }
+    }
+    Ok(overlays)
+}
+
+fn parse_flags(value: &str) -> Result<u32, String> {
+    let parsed = if let Some(hex) = value.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+    }
+    else {
+        value.parse()
+    };
+    parsed.map_err(|_| format!("--debug-flags expects a decimal or 0x hex mask but got '{}'", value))
+}
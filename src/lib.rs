@@ -0,0 +1,8 @@
+extern crate generic_game as gg;
+extern crate nalgebra as na;
+extern crate num;
+extern crate png;
+#[macro_use]
+extern crate lazy_static;
+
+pub mod tree_game;
@@ -1,30 +1,177 @@
-#![feature(set_stdio)]
 extern crate generic_game as gg;
-extern crate nalgebra as na;
 extern crate time;
-extern crate num;
-#[macro_use]
-extern crate lazy_static;
+extern crate tree_game;
+
+mod cli;
 
 use gg::debug::*;
 use gg::{debug, rendering, input, window, handlerbasic, games, Handler};
 use std::env;
 use std::io::*;
-mod tree_game;
+use std::panic;
+use std::process;
+use tree_game::tree_game::{TreeGame, GameSetup, Theme};
+use tree_game::tree_game::headless::HeadlessRunner;
+use tree_game::tree_game::replay::Replay;
+use tree_game::tree_game::tree::TreeData;
+use tree_game::tree_game::svg_export::SvgOptions;
+use tree_game::tree_game::image::Image;
+use tree_game::tree_game::raster::RasterView;
+use cli::{Command, Options, USAGE, EXIT_FAILURE, EXIT_USAGE};
+
+const HEADLESS_T_STEP: f64 = 1.0 / 60.0;
+// Share of a snapshot's pixels that may differ from the golden image, e.g. along shape edges
+const MAX_MISMATCHED_FRACTION: f64 = 0.001;
 
 fn main() {
+    process::exit(run());
+}
+
+// Returns the exit code, so that the game is dropped (and any recording saved) before the process exits
+fn run() -> i32 {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match cli::parse_args(&args) {
+        Ok(Command::Run(options)) => *options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return 0;
+        },
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
     env::set_var("RUST_BACKTRACE", "full");
-    debug::set_flags(DEFAULTDEBUG);
+    debug::set_flags(options.debug_flags);
     debug(&format!("Starting Up - Date: {}", time::now_utc().ctime()));
-    let error_writer = Box::new(ErrorWriter::new());
-    set_panic(Some(error_writer));
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = writeln!(ErrorWriter::new(), "{}", info);
+        default_hook(info);
+    }));
+
+    let game = match create_game(&options) {
+        Ok(game) => game,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_FAILURE;
+        }
+    };
+
+    if let Some(ref path) = options.svg_file {
+        let svg_options = SvgOptions {
+            show_labels: options.svg_overlays.labels,
+            show_connections: options.svg_overlays.connections,
+            show_entities: options.svg_overlays.entities,
+            theme: game.theme.clone(),
+            ..Default::default()
+        };
+        if let Err(err) = game.get_svg_export(svg_options).save(path) {
+            eprintln!("Could not write {}: {}", path, err);
+            return EXIT_FAILURE;
+        }
+    }
+    else if let Some(ref path) = options.replay_file {
+        let result = match Replay::load(path).and_then(|replay| replay.play(game)) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("{}", err);
+                return EXIT_FAILURE;
+            }
+        };
+        match result.divergence {
+            None => println!("Replay matched over {} frames", result.frames),
+            Some(divergence) => {
+                println!("Replay diverged: {}", divergence);
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    else if options.headless_frames.is_some() || options.snapshot_file.is_some() {
+        let game = run_headless(game, options.headless_frames.unwrap_or(0));
+        if let Some(ref path) = options.snapshot_file {
+            if let Err(message) = take_snapshot(&game, path, &options) {
+                eprintln!("{}", message);
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    else {
+        run_windowed(game, &options);
+    }
+    0
+}
+
+fn create_game(options: &Options) -> std::result::Result<TreeGame, String> {
+    let mut setup = match options.setup_file {
+        Some(ref path) => GameSetup::load(path).map_err(|err| err.to_string())?,
+        None => GameSetup::load("setup.cfg").unwrap_or_else(|err| {
+            debug(&format!("Using the default setup: {}", err));
+            GameSetup::default()
+        })
+    };
+    if let Some(layers) = options.layers {
+        setup.num_layers = layers;
+    }
+    if options.seed.is_some() {
+        setup.seed = options.seed;
+    }
+
+    let mut game = match options.level_file {
+        Some(ref path) => TreeGame::from_level(setup, TreeData::load_level(path).map_err(|err| err.to_string())?),
+        None => TreeGame::new(setup)
+    }.map_err(|err| err.to_string())?;
+    if let Some(ref theme) = options.theme {
+        match Theme::built_in(theme) {
+            Some(theme) => game.set_theme(theme),
+            None => game.set_theme_file(theme).map_err(|err| err.to_string())?
+        }
+    }
+    if let Some(ref path) = options.record_file {
+        game.record_to(path);
+    }
+    Ok(game)
+}
+
+fn run_headless(game: TreeGame, frames: usize) -> TreeGame {
+    let mut runner = HeadlessRunner::new(game, HEADLESS_T_STEP);
+    for _ in 0..frames {
+        runner.step(&Default::default());
+    }
+    let report = runner.get_report();
+    println!("Ran {} frames ({:.2}s)", report.frames, report.elapsed);
+    println!("Final position: {:?}", report.final_position);
+    println!("Final state: {:?}", report.state);
+    runner.game
+}
+
+// Writes the snapshot, then checks it against the golden image if there is one
+fn take_snapshot(game: &TreeGame, path: &str, options: &Options) -> std::result::Result<(), String> {
+    let (width, height) = options.window_size;
+    let image = game.snapshot(width, height, RasterView::from_camera(&game.camera));
+    image.save_png(path).map_err(|err| err.to_string())?;
+
+    if let Some(ref golden_path) = options.golden_file {
+        let golden = Image::load_png(golden_path).map_err(|err| err.to_string())?;
+        let diff = image.compare(&golden, options.tolerance);
+        if !diff.passes(MAX_MISMATCHED_FRACTION) {
+            let diff_path = format!("{}.diff.png", path);
+            image.get_difference_image(&golden, options.tolerance).save_png(&diff_path).map_err(|err| err.to_string())?;
+            return Err(format!("Snapshot does not match {}: {} (differences drawn to {})", golden_path, diff, diff_path));
+        }
+        println!("Snapshot matches {}: {}", golden_path, diff);
+    }
+    Ok(())
+}
 
-    let renderer: Box<rendering::Renderer> = Box::new(rendering::glium_renderer::GliumRenderer::new((1600, 1024)));
-    let input_handler: Box<input::InputHandler> = Box::new(input::multihandler::MultiInput::new());
-    let window_handler: Box<window::WindowHandler> = Box::new(window::GlutinInput::new());
+fn run_windowed(game: TreeGame, options: &Options) {
+    let renderer: Box<dyn rendering::Renderer> = Box::new(rendering::glium_renderer::GliumRenderer::new(options.window_size));
+    let input_handler: Box<dyn input::InputHandler> = Box::new(input::multihandler::MultiInput::new());
+    let window_handler: Box<dyn window::WindowHandler> = Box::new(window::GlutinInput::new());
 
-    let game: Box<games::Game> = Box::new(tree_game::TreeGame::new(Default::default()));
-    let mut handler: Box<Handler> = Box::new(handlerbasic::HandlerBasic::new(renderer, input_handler, window_handler, game));
+    let game: Box<dyn games::Game> = Box::new(game);
+    let mut handler: Box<dyn Handler> = Box::new(handlerbasic::HandlerBasic::new(renderer, input_handler, window_handler, game));
 
     handler.init();
     while !handler.exit() {
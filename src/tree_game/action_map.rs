@@ -0,0 +1,394 @@
+// Maps game actions onto keyboard keys, gamepad buttons and stick directions. Config files list
+// one binding per line, e.g.
+//
+//   move_left key left
+//   move_left axis x-
+//   jump_branch button a
+use super::gamepad::{GamepadState, GamepadButton};
+use gg::input::KeyboardInput;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    JumpBranch,
+    Interact,
+    Pause,
+    NextTheme
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveLeft, Action::MoveRight, Action::MoveUp, Action::MoveDown,
+        Action::JumpBranch, Action::Interact, Action::Pause, Action::NextTheme
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::JumpBranch => "jump_branch",
+            Action::Interact => "interact",
+            Action::Pause => "pause",
+            Action::NextTheme => "next_theme"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|action| action.name() == name)
+    }
+}
+
+pub const KEY_COUNT: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Key {
+    Left, Right, Up, Down,
+    Space, Enter, Escape,
+    W, A, S, D, Z, Q, E, F, P
+}
+
+impl Key {
+    pub const ALL: [Key; KEY_COUNT] = [
+        Key::Left, Key::Right, Key::Up, Key::Down,
+        Key::Space, Key::Enter, Key::Escape,
+        Key::W, Key::A, Key::S, Key::D, Key::Z, Key::Q, Key::E, Key::F, Key::P
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Key::Left => "left",
+            Key::Right => "right",
+            Key::Up => "up",
+            Key::Down => "down",
+            Key::Space => "space",
+            Key::Enter => "enter",
+            Key::Escape => "escape",
+            Key::W => "w",
+            Key::A => "a",
+            Key::S => "s",
+            Key::D => "d",
+            Key::Z => "z",
+            Key::Q => "q",
+            Key::E => "e",
+            Key::F => "f",
+            Key::P => "p"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Key> {
+        Key::ALL.iter().cloned().find(|key| key.name() == name)
+    }
+
+    pub fn is_pressed(&self, kbd: &KeyboardInput) -> bool {
+        match *self {
+            Key::Left => kbd.left,
+            Key::Right => kbd.right,
+            Key::Up => kbd.up,
+            Key::Down => kbd.down,
+            Key::Space => kbd.space,
+            Key::Enter => kbd.enter,
+            Key::Escape => kbd.escape,
+            Key::W => kbd.w,
+            Key::A => kbd.a,
+            Key::S => kbd.s,
+            Key::D => kbd.d,
+            Key::Z => kbd.z,
+            Key::Q => kbd.q,
+            Key::E => kbd.e,
+            Key::F => kbd.f,
+            Key::P => kbd.p
+        }
+    }
+
+    pub fn set_pressed(&self, kbd: &mut KeyboardInput, pressed: bool) {
+        match *self {
+            Key::Left => kbd.left = pressed,
+            Key::Right => kbd.right = pressed,
+            Key::Up => kbd.up = pressed,
+            Key::Down => kbd.down = pressed,
+            Key::Space => kbd.space = pressed,
+            Key::Enter => kbd.enter = pressed,
+            Key::Escape => kbd.escape = pressed,
+            Key::W => kbd.w = pressed,
+            Key::A => kbd.a = pressed,
+            Key::S => kbd.s = pressed,
+            Key::D => kbd.d = pressed,
+            Key::Z => kbd.z = pressed,
+            Key::Q => kbd.q = pressed,
+            Key::E => kbd.e = pressed,
+            Key::F => kbd.f = pressed,
+            Key::P => kbd.p = pressed
+        }
+    }
+}
+
+// One direction of the (already deadzoned) left stick
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StickAxis {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY
+}
+
+impl StickAxis {
+    pub const ALL: [StickAxis; 4] = [StickAxis::PositiveX, StickAxis::NegativeX, StickAxis::PositiveY, StickAxis::NegativeY];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StickAxis::PositiveX => "x+",
+            StickAxis::NegativeX => "x-",
+            StickAxis::PositiveY => "y+",
+            StickAxis::NegativeY => "y-"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<StickAxis> {
+        StickAxis::ALL.iter().cloned().find(|axis| axis.name() == name)
+    }
+
+    pub fn get_value(&self, gamepad: &GamepadState) -> f64 {
+        match *self {
+            StickAxis::PositiveX => gamepad.stick.x.max(0.0),
+            StickAxis::NegativeX => (-gamepad.stick.x).max(0.0),
+            StickAxis::PositiveY => gamepad.stick.y.max(0.0),
+            StickAxis::NegativeY => (-gamepad.stick.y).max(0.0)
+        }
+    }
+}
+
+fn button_name(button: GamepadButton) -> &'static str {
+    match button {
+        GamepadButton::A => "a",
+        GamepadButton::B => "b",
+        GamepadButton::X => "x",
+        GamepadButton::Y => "y",
+        GamepadButton::Start => "start"
+    }
+}
+
+fn button_from_name(name: &str) -> Option<GamepadButton> {
+    [GamepadButton::A, GamepadButton::B, GamepadButton::X, GamepadButton::Y, GamepadButton::Start]
+        .iter()
+        .cloned()
+        .find(|&button| button_name(button) == name)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Binding {
+    Key(Key),
+    Button(GamepadButton),
+    Axis(StickAxis)
+}
+
+impl Binding {
+    pub fn is_keyboard(&self) -> bool {
+        matches!(*self, Binding::Key(_))
+    }
+
+    // How strongly the binding is held, from 0 to 1
+    pub fn get_value(&self, kbd: &KeyboardInput, gamepad: &GamepadState) -> f64 {
+        match *self {
+            Binding::Key(key) => if key.is_pressed(kbd) { 1.0 } else { 0.0 },
+            Binding::Button(button) => if button.is_pressed(gamepad) { 1.0 } else { 0.0 },
+            Binding::Axis(axis) => axis.get_value(gamepad)
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Binding::Key(key) => write!(f, "key {}", key.name()),
+            Binding::Button(button) => write!(f, "button {}", button_name(button)),
+            Binding::Axis(axis) => write!(f, "axis {}", axis.name())
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BindingConflict {
+    pub binding: Binding,
+    pub existing: Action,
+    pub requested: Action
+}
+
+impl fmt::Display for BindingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' is already bound to {} so cannot also be bound to {}", self.binding, self.existing.name(), self.requested.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    Conflict { line: usize, conflict: BindingConflict }
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ActionMapError::Io(ref err) => write!(f, "Could not access bindings file: {}", err),
+            ActionMapError::Parse { line, ref message } => write!(f, "Bindings file line {}: {}", line, message),
+            ActionMapError::Conflict { line, conflict } => write!(f, "Bindings file line {}: {}", line, conflict)
+        }
+    }
+}
+
+impl From<io::Error> for ActionMapError {
+    fn from(err: io::Error) -> Self {
+        ActionMapError::Io(err)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ActionMap {
+    bindings: Vec<(Action, Binding)>
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap {
+            bindings: Vec::new()
+        }
+    }
+
+    pub fn get_bindings(&self, action: Action) -> Vec<Binding> {
+        self.bindings
+            .iter()
+            .filter(|&&(bound_action, _)| bound_action == action)
+            .map(|&(_, binding)| binding)
+            .collect()
+    }
+
+    pub fn get_action(&self, binding: Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(_, existing)| existing == binding)
+            .map(|&(action, _)| action)
+    }
+
+    // Fails rather than letting one input trigger two actions
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Result<(), BindingConflict> {
+        match self.get_action(binding) {
+            Some(existing) if existing == action => Ok(()),
+            Some(existing) => Err(BindingConflict { binding, existing, requested: action }),
+            None => {
+                self.bindings.push((action, binding));
+                Ok(())
+            }
+        }
+    }
+
+    // Moves the binding to this action, whatever it was bound to before
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.unbind(binding);
+        self.bindings.push((action, binding));
+    }
+
+    pub fn unbind(&mut self, binding: Binding) {
+        self.bindings.retain(|&(_, existing)| existing != binding);
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.retain(|&(existing, _)| existing != action);
+    }
+
+    // The strongest of the action's bindings on one device, from 0 to 1
+    pub fn get_value(&self, action: Action, kbd: &KeyboardInput, gamepad: &GamepadState, keyboard: bool) -> f64 {
+        self.bindings
+            .iter()
+            .filter(|&&(bound_action, binding)| bound_action == action && binding.is_keyboard() == keyboard)
+            .map(|&(_, binding)| binding.get_value(kbd, gamepad))
+            .fold(0.0, f64::max)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_config_string().as_bytes())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ActionMap, ActionMapError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        ActionMap::from_config_str(&contents)
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let mut output = String::new();
+        for action in Action::ALL.iter() {
+            for binding in self.get_bindings(*action) {
+                output += &format!("{} {}\n", action.name(), binding);
+            }
+        }
+        output
+    }
+
+    pub fn from_config_str(contents: &str) -> Result<ActionMap, ActionMapError> {
+        let mut map = ActionMap::new();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (action, binding) = parse_binding(line).map_err(|message| ActionMapError::Parse { line: line_number, message })?;
+            map.bind(action, binding).map_err(|conflict| ActionMapError::Conflict { line: line_number, conflict })?;
+        }
+        Ok(map)
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let defaults = [
+            (Action::MoveLeft, Binding::Key(Key::Left)),
+            (Action::MoveRight, Binding::Key(Key::Right)),
+            (Action::MoveUp, Binding::Key(Key::Up)),
+            (Action::MoveDown, Binding::Key(Key::Down)),
+            (Action::JumpBranch, Binding::Key(Key::Space)),
+            (Action::Interact, Binding::Key(Key::E)),
+            (Action::Pause, Binding::Key(Key::Escape)),
+            (Action::NextTheme, Binding::Key(Key::P)),
+            (Action::MoveLeft, Binding::Axis(StickAxis::NegativeX)),
+            (Action::MoveRight, Binding::Axis(StickAxis::PositiveX)),
+            (Action::MoveUp, Binding::Axis(StickAxis::PositiveY)),
+            (Action::MoveDown, Binding::Axis(StickAxis::NegativeY)),
+            (Action::JumpBranch, Binding::Button(GamepadButton::A)),
+            (Action::Interact, Binding::Button(GamepadButton::X)),
+            (Action::Pause, Binding::Button(GamepadButton::Start)),
+            (Action::NextTheme, Binding::Button(GamepadButton::Y))
+        ];
+        ActionMap {
+            bindings: defaults.to_vec()
+        }
+    }
+}
+
+fn parse_binding(line: &str) -> Result<(Action, Binding), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.len() != 3 {
+        return Err(format!("Expected '<action> <key|button|axis> <name>' but found '{}'", line));
+    }
+    let action = Action::from_name(words[0]).ok_or_else(|| format!("Unknown action '{}'", words[0]))?;
+    let binding = match words[1] {
+        "key" => Key::from_name(words[2]).map(Binding::Key),
+        "button" => button_from_name(words[2]).map(Binding::Button),
+        "axis" => StickAxis::from_name(words[2]).map(Binding::Axis),
+        kind => return Err(format!("Unknown binding type '{}'", kind))
+    };
+    binding
+        .map(|binding| (action, binding))
+        .ok_or_else(|| format!("Unknown {} '{}'", words[1], words[2]))
+}
@@ -0,0 +1,93 @@
+use na::Vector2;
+
+#[derive(Copy, Clone, Debug)]
+pub struct CameraSettings {
+    // Height of the view when following a branch on the first layer
+    pub base_viewport_height: f64,
+    // The view height is multiplied by this for each layer deeper the target is
+    pub zoom_per_layer: f64,
+    pub min_viewport_height: f64,
+    // Fraction of the remaining distance covered per second
+    pub follow_rate: f64,
+    pub zoom_rate: f64,
+    // Half extents, as fractions of the view height, of the box the target can move in without the camera following
+    pub deadzone: Vector2<f64>
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            base_viewport_height: 2.0,
+            zoom_per_layer: 0.6,
+            min_viewport_height: 0.05,
+            follow_rate: 4.0,
+            zoom_rate: 2.0,
+            deadzone: Vector2::new(0.1, 0.1)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Camera {
+    pub settings: CameraSettings,
+    pos: Vector2<f64>,
+    viewport_height: f64
+}
+
+impl Camera {
+    pub fn new(settings: CameraSettings, pos: Vector2<f64>) -> Camera {
+        Camera {
+            settings,
+            pos,
+            viewport_height: settings.base_viewport_height
+        }
+    }
+
+    pub fn get_pos(&self) -> Vector2<f64> {
+        self.pos
+    }
+
+    pub fn get_viewport_height(&self) -> f64 {
+        self.viewport_height
+    }
+
+    pub fn get_target_viewport_height(&self, layer: usize) -> f64 {
+        (self.settings.base_viewport_height * self.settings.zoom_per_layer.powi(layer as i32))
+            .max(self.settings.min_viewport_height)
+    }
+
+    // Jumps straight to the target without easing, e.g. after a respawn
+    pub fn snap_to(&mut self, target_pos: Vector2<f64>, layer: usize) {
+        self.pos = target_pos;
+        self.viewport_height = self.get_target_viewport_height(layer);
+    }
+
+    pub fn update(&mut self, target_pos: Vector2<f64>, layer: usize, t_step: f64) {
+        let deadzone = self.settings.deadzone * self.viewport_height;
+        let offset = target_pos - self.pos;
+        let desired_pos = self.pos + Vector2::new(
+            outside_deadzone(offset.x, deadzone.x),
+            outside_deadzone(offset.y, deadzone.y)
+        );
+
+        let follow = (self.settings.follow_rate * t_step).min(1.0);
+        self.pos += (desired_pos - self.pos) * follow;
+
+        let zoom = (self.settings.zoom_rate * t_step).min(1.0);
+        let target_height = self.get_target_viewport_height(layer);
+        self.viewport_height += (target_height - self.viewport_height) * zoom;
+    }
+}
+
+// How far the offset reaches past the edge of the deadzone
+fn outside_deadzone(offset: f64, half_extent: f64) -> f64 {
+    if offset > half_extent {
+        offset - half_extent
+    }
+    else if offset < -half_extent {
+        offset + half_extent
+    }
+    else {
+        0.0
+    }
+}
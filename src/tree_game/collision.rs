@@ -0,0 +1,84 @@
+// Overlap tests between objects on the tree, using every branch their footprint covers
+use super::movable::Movable;
+use super::footprint::get_footprint;
+use super::tree::{Tree, BranchRect};
+use na::Vector4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EntityId {
+    Player,
+    Enemy(usize),
+    Pickup(usize)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub first: EntityId,
+    pub second: EntityId
+}
+
+impl CollisionEvent {
+    pub fn involves(&self, entity: EntityId) -> bool {
+        self.first == entity || self.second == entity
+    }
+
+    // The entity on the other side of the collision from this one
+    pub fn get_other(&self, entity: EntityId) -> Option<EntityId> {
+        if self.first == entity {
+            Some(self.second)
+        }
+        else if self.second == entity {
+            Some(self.first)
+        }
+        else {
+            None
+        }
+    }
+}
+
+pub trait Collidable: Movable {
+    fn get_length(&self) -> f64;
+
+    fn get_height(&self) -> f64;
+
+    // Objects that are not on the tree touch nothing
+    fn get_collision_rects(&self, tree: &dyn Tree) -> Vec<BranchRect> {
+        get_footprint(tree, self.get_position(), self.get_length(), self.get_height(), Vector4::new(0.0, 0.0, 0.0, 0.0))
+            .unwrap_or_default()
+    }
+}
+
+pub fn rects_overlap(first: &BranchRect, second: &BranchRect) -> bool {
+    let offset = first.pos - second.pos;
+    first.branch_id == second.branch_id
+        && offset.x.abs() < (first.length + second.length) / 2.0
+        && offset.y.abs() < (first.height + second.height) / 2.0
+}
+
+pub fn footprints_overlap(first: &[BranchRect], second: &[BranchRect]) -> bool {
+    first
+        .iter()
+        .any(|first_rect| second.iter().any(|second_rect| rects_overlap(first_rect, second_rect)))
+}
+
+pub fn overlaps(tree: &dyn Tree, first: &dyn Collidable, second: &dyn Collidable) -> bool {
+    footprints_overlap(&first.get_collision_rects(tree), &second.get_collision_rects(tree))
+}
+
+// Every overlapping pair, each reported once
+pub fn detect_collisions(tree: &dyn Tree, entities: &[(EntityId, &dyn Collidable)]) -> Vec<CollisionEvent> {
+    let footprints: Vec<(EntityId, Vec<BranchRect>)> = entities
+        .iter()
+        .map(|&(id, entity)| (id, entity.get_collision_rects(tree)))
+        .collect();
+
+    let mut events = Vec::new();
+    for (index, &(first, ref first_rects)) in footprints.iter().enumerate() {
+        for &(second, ref second_rects) in footprints.iter().skip(index + 1) {
+            if footprints_overlap(first_rects, second_rects) {
+                events.push(CollisionEvent { first, second });
+            }
+        }
+    }
+    events
+}
@@ -0,0 +1,167 @@
+use super::position::Position;
+use super::tree::{Tree, TreeError, BranchObject, Boundary, SeededRng};
+use super::movable::Movable;
+use super::footprint::get_footprint;
+use super::collision::Collidable;
+use super::theme::Theme;
+use super::raster::RasterShape;
+use na::Vector2;
+
+pub use super::tree::EnemyBehaviour;
+
+// Chance per second that a wandering enemy turns around or picks a new drift
+const WANDER_TURN_RATE: f64 = 0.5;
+
+pub struct Enemy {
+    pos: Position,
+    length: f64,
+    height: f64,
+    speed: f64,
+    behaviour: EnemyBehaviour,
+    direction: Vector2<f64>,
+    rng: SeededRng
+}
+
+impl Enemy {
+    pub fn new(pos: Position, length: f64, height: f64, speed: f64, behaviour: EnemyBehaviour, seed: u64) -> Enemy {
+        Enemy {
+            pos,
+            length,
+            height,
+            speed,
+            behaviour,
+            direction: Vector2::new(1.0, 0.0),
+            rng: SeededRng::new(seed)
+        }
+    }
+
+    pub fn get_behaviour(&self) -> EnemyBehaviour {
+        self.behaviour
+    }
+
+    pub fn update(&mut self, tree: &dyn Tree, player_pos: Position, t_step: f64) -> Result<(), TreeError> {
+        match self.behaviour {
+            EnemyBehaviour::Patrol => self.patrol(tree, t_step),
+            EnemyBehaviour::Wander => self.wander(tree, t_step),
+            EnemyBehaviour::Chase => self.chase(tree, player_pos, t_step)
+        }
+    }
+
+    fn patrol(&mut self, tree: &dyn Tree, t_step: f64) -> Result<(), TreeError> {
+        let branch_length = tree.try_get_branch(self.pos.get_branch_id())?.get_logical().length;
+        let min_x = (self.length / 2.0).min(branch_length / 2.0);
+        let max_x = branch_length - min_x;
+
+        let mut branch_pos = self.pos.get_branch_position();
+        branch_pos.x += self.direction.x * self.speed * t_step;
+        if branch_pos.x > max_x {
+            branch_pos.x = max_x;
+            self.direction.x = -1.0;
+        }
+        else if branch_pos.x < min_x {
+            branch_pos.x = min_x;
+            self.direction.x = 1.0;
+        }
+        self.pos.set_branch_position(branch_pos);
+        Ok(())
+    }
+
+    fn wander(&mut self, tree: &dyn Tree, t_step: f64) -> Result<(), TreeError> {
+        if self.rng.chance(WANDER_TURN_RATE * t_step) {
+            self.direction.x = -self.direction.x;
+        }
+        if self.rng.chance(WANDER_TURN_RATE * t_step) {
+            self.direction.y = self.rng.range(-0.5, 0.5);
+        }
+
+        let report = self.change_position(tree, self.direction * self.speed * t_step)?;
+        if report.blocked {
+            self.direction.x = -self.direction.x;
+        }
+        if report.hit_wall {
+            self.direction.y = -self.direction.y;
+        }
+        Ok(())
+    }
+
+    fn chase(&mut self, tree: &dyn Tree, player_pos: Position, t_step: f64) -> Result<(), TreeError> {
+        let step = self.speed * t_step;
+        let current_id = self.pos.get_branch_id();
+        let current_pos = self.pos.get_branch_position();
+
+        let path = match tree.get_path(current_id, player_pos.get_branch_id())? {
+            Some(path) => path,
+            None => return Ok(())
+        };
+
+        if path.len() == 1 {
+            let offset = player_pos.get_branch_position() - current_pos;
+            let distance = offset.norm();
+            let change = if distance > step { offset * step / distance } else { offset };
+            self.change_position(tree, change)?;
+            return Ok(());
+        }
+
+        // Head for the middle of the boundary shared with the next branch on the path
+        let current_branch = tree.try_get_branch(current_id)?;
+        let connection = current_branch
+            .get_connections()
+            .iter()
+            .find(|c| c.get_branch_id() == path[1])
+            .ok_or(TreeError::MissingBranch(path[1]))?;
+        let interval = current_branch.get_logical_boundary_interval(connection.get_boundary());
+        let target_y = (interval.get_start() + interval.get_end()) / 2.0;
+        let x_direction = match connection.get_boundary() {
+            Boundary::Left(_, _) => -1.0,
+            Boundary::Right(_, _) => 1.0
+        };
+
+        let y_change = (target_y - current_pos.y).max(-step).min(step);
+        let boundary_x = if x_direction < 0.0 { 0.0 } else { current_branch.get_logical().length };
+        let x_room = (boundary_x - current_pos.x).abs();
+        // Only cross once lined up with the boundary, so the right branch is entered
+        let x_change = if interval.contains(current_pos.y + y_change) { step } else { (x_room - self.length / 2.0).max(0.0).min(step) };
+
+        self.change_position(tree, Vector2::new(x_direction * x_change, y_change))?;
+        Ok(())
+    }
+
+    pub fn get_branch_objects(&self, tree: &dyn Tree, theme: &Theme) -> Vec<Box<dyn BranchObject>> {
+        get_footprint(tree, self.pos, self.length, self.height, theme.enemy)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|part| -> Box<dyn BranchObject> { Box::new(part) })
+            .collect()
+    }
+
+    pub fn get_raster_shapes<T: Tree> (&self, tree: &T, theme: &Theme) -> Vec<RasterShape> {
+        self.get_branch_objects(tree, theme)
+            .into_iter()
+            .flat_map(|p| {p.get_raster_shapes(tree)})
+            .collect()
+    }
+}
+
+impl Movable for Enemy {
+    fn get_position(&self) -> Position {
+        self.pos
+    }
+
+    fn set_position(&mut self, new_pos: Position) {
+        self.pos = new_pos;
+    }
+
+    fn get_half_height(&self) -> f64 {
+        self.height / 2.0
+    }
+}
+
+impl Collidable for Enemy {
+    fn get_length(&self) -> f64 {
+        self.length
+    }
+
+    fn get_height(&self) -> f64 {
+        self.height
+    }
+}
@@ -0,0 +1,71 @@
+// The rectangles an object covers; an object near the end of a branch spills over onto the
+// branches connected there
+use super::position::Position;
+use super::tree::{Tree, TreeError, BranchRect, ConnectionIntersect, Boundary};
+use na::{Vector2, Vector4};
+use gg::geometry::Line;
+
+pub fn get_footprint(tree: &dyn Tree, pos: Position, length: f64, height: f64, color: Vector4<f64>) -> Result<Vec<BranchRect>, TreeError> {
+    let current_branch = tree.try_get_branch(pos.get_branch_id())?;
+
+    let mut output = Vec::new();
+
+    let current_branch_pos = pos.get_branch_position();
+
+    let centre_part = BranchRect {
+        branch_id: pos.get_branch_id(),
+        length,
+        height,
+        pos: current_branch_pos,
+        color
+    };
+
+    output.push(centre_part);
+
+    let center_right_line = Line::new(
+        current_branch_pos,
+        current_branch_pos + Vector2::new(length / 2.0, 0.0)
+    );
+
+    if let Some(intersect) = current_branch.get_boundary_intersect(center_right_line) {
+        output.push(get_side_rect(tree, pos, height, color, intersect)?);
+    }
+
+    let center_left_line = Line::new(
+        current_branch_pos,
+        current_branch_pos - Vector2::new(length / 2.0, 0.0)
+    );
+
+    if let Some(intersect) = current_branch.get_boundary_intersect(center_left_line) {
+        output.push(get_side_rect(tree, pos, height, color, intersect)?);
+    }
+
+    Ok(output)
+}
+
+fn get_side_rect(tree: &dyn Tree, pos: Position, height: f64, color: Vector4<f64>, intersect: ConnectionIntersect) -> Result<BranchRect, TreeError> {
+    let current_branch = tree.try_get_branch(pos.get_branch_id())?;
+    let current_branch_pos = pos.get_branch_position();
+
+    let side_length = intersect.overlap;
+    let current_branch_interval = current_branch.get_logical_boundary_interval(intersect.connection.get_boundary());
+
+    let side_branch = tree.try_get_branch(intersect.connection.get_branch_id())?;
+    let side_connection = tree.try_get_matching_connection(current_branch.get_id(), &intersect.connection)?;
+    let side_branch_interval = side_branch.get_logical_boundary_interval(side_connection.get_boundary());
+
+    let new_y_pos = current_branch_interval.fit_point_to(current_branch_pos.y, side_branch_interval);
+    let new_x_pos = match intersect.connection.get_boundary(){
+        Boundary::Left(_, _) => side_branch.get_logical().length - intersect.overlap / 2.0,
+        Boundary::Right(_, _) => intersect.overlap / 2.0
+    };
+    let side_position = Vector2::new(new_x_pos, new_y_pos);
+
+    Ok(BranchRect {
+        branch_id: side_branch.get_id(),
+        length: side_length,
+        height,
+        pos: side_position,
+        color
+    })
+}
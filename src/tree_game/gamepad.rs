@@ -0,0 +1,103 @@
+use gg::input::JoystickInput;
+use na::Vector2;
+
+pub const BUTTON_COUNT: usize = 5;
+
+// The parts of JoystickInput the game reads, in a form that can be stored and compared.
+// Buttons are in the order a, b, x, y, start
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GamepadState {
+    pub stick: Vector2<f64>,
+    pub buttons: [bool; BUTTON_COUNT]
+}
+
+impl GamepadState {
+    pub fn apply_to(&self, joystick: &mut JoystickInput) {
+        joystick.left_x = self.stick.x;
+        joystick.left_y = self.stick.y;
+        joystick.a = self.buttons[0];
+        joystick.b = self.buttons[1];
+        joystick.x = self.buttons[2];
+        joystick.y = self.buttons[3];
+        joystick.start = self.buttons[4];
+    }
+}
+
+impl From<&JoystickInput> for GamepadState {
+    fn from(joystick: &JoystickInput) -> Self {
+        GamepadState {
+            stick: Vector2::new(joystick.left_x, joystick.left_y),
+            buttons: [joystick.a, joystick.b, joystick.x, joystick.y, joystick.start]
+        }
+    }
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        GamepadState {
+            stick: Vector2::new(0.0, 0.0),
+            buttons: [false; BUTTON_COUNT]
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    Start
+}
+
+impl GamepadButton {
+    pub fn is_pressed(&self, state: &GamepadState) -> bool {
+        let index = match *self {
+            GamepadButton::A => 0,
+            GamepadButton::B => 1,
+            GamepadButton::X => 2,
+            GamepadButton::Y => 3,
+            GamepadButton::Start => 4
+        };
+        state.buttons[index]
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StickSettings {
+    // Stick deflections (0 to 1) below this are ignored
+    pub deadzone: f64,
+    // 1 responds linearly, higher values give finer control near the centre
+    pub response_exponent: f64
+}
+
+impl StickSettings {
+    pub fn apply(&self, stick: Vector2<f64>) -> Vector2<f64> {
+        let deflection = stick.norm().min(1.0);
+        if deflection <= self.deadzone {
+            return Vector2::new(0.0, 0.0);
+        }
+        let rescaled = (deflection - self.deadzone) / (1.0 - self.deadzone);
+        stick / stick.norm() * rescaled.powf(self.response_exponent)
+    }
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            deadzone: 0.2,
+            response_exponent: 1.5
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GamepadSettings {
+    pub stick: StickSettings
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad
+}
@@ -0,0 +1,132 @@
+// Steps a TreeGame without a renderer or window, e.g. for CI and integration tests
+use super::{TreeGame, GameState, GamePhase, ExternalInput};
+use super::position::Position;
+use super::movable::Movable;
+use super::physics::Physical;
+use gg::input::KeyboardInput;
+use na::Vector2;
+
+// Keys held down for a number of frames
+#[derive(Clone)]
+pub struct ScriptStep {
+    pub frames: usize,
+    pub kbd: KeyboardInput
+}
+
+impl ScriptStep {
+    pub fn new(frames: usize, kbd: KeyboardInput) -> ScriptStep {
+        ScriptStep {
+            frames,
+            kbd
+        }
+    }
+
+    pub fn idle(frames: usize) -> ScriptStep {
+        ScriptStep::new(frames, KeyboardInput::default())
+    }
+
+    // Holds the arrow keys pointing the same way as the direction
+    pub fn moving(frames: usize, direction: Vector2<f64>) -> ScriptStep {
+        let kbd = KeyboardInput {
+            right: direction.x > 0.0,
+            left: direction.x < 0.0,
+            up: direction.y > 0.0,
+            down: direction.y < 0.0,
+            ..Default::default()
+        };
+        ScriptStep::new(frames, kbd)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FrameRecord {
+    pub frame: usize,
+    pub time: f64,
+    pub position: Position,
+    pub velocity: Vector2<f64>
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationReport {
+    pub frames: usize,
+    pub elapsed: f64,
+    pub final_position: Position,
+    pub final_velocity: Vector2<f64>,
+    pub state: GameState,
+    pub trajectory: Vec<FrameRecord>
+}
+
+pub struct HeadlessRunner {
+    pub game: TreeGame,
+    t_step: f64,
+    frame: usize,
+    elapsed: f64,
+    trajectory: Vec<FrameRecord>
+}
+
+impl HeadlessRunner {
+    // Starts playing straight away, as there is nobody to get past the title screen
+    pub fn new(game: TreeGame, t_step: f64) -> HeadlessRunner {
+        HeadlessRunner::with_phase(game, t_step, GamePhase::Playing)
+    }
+
+    pub fn with_phase(mut game: TreeGame, t_step: f64, phase: GamePhase) -> HeadlessRunner {
+        game.init();
+        game.state.phase = phase;
+        HeadlessRunner {
+            game,
+            t_step,
+            frame: 0,
+            elapsed: 0.0,
+            trajectory: Vec::new()
+        }
+    }
+
+    pub fn get_t_step(&self) -> f64 {
+        self.t_step
+    }
+
+    pub fn step(&mut self, kbd: &KeyboardInput) {
+        let input = ExternalInput {
+            kbd: kbd.clone(),
+            ..Default::default()
+        };
+        let t_step = self.t_step;
+        self.step_input(&input, t_step);
+    }
+
+    pub fn step_input(&mut self, input: &ExternalInput, t_step: f64) {
+        self.game.external_input = input.clone();
+        self.game.update_input();
+        self.game.update_logic(t_step);
+
+        self.frame += 1;
+        self.elapsed += t_step;
+        self.trajectory.push(FrameRecord {
+            frame: self.frame,
+            time: self.elapsed,
+            position: self.game.player.get_position(),
+            velocity: self.game.player.get_body().velocity
+        });
+    }
+
+    pub fn run_script(&mut self, script: &[ScriptStep]) -> SimulationReport {
+        for script_step in script {
+            for _ in 0..script_step.frames {
+                self.step(&script_step.kbd);
+            }
+        }
+        self.get_report()
+    }
+
+    pub fn get_report(&self) -> SimulationReport {
+        SimulationReport {
+            frames: self.frame,
+            elapsed: self.elapsed,
+            final_position: self.game.player.get_position(),
+            final_velocity: self.game.player.get_body().velocity,
+            state: self.game.state,
+            trajectory: self.trajectory.clone()
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// RGBA images that can be written to and read from PNG files, and compared against each other
+// for golden image checks
+use na::Vector4;
+use png;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Encoding(png::EncodingError),
+    Decoding(png::DecodingError),
+    Unsupported(String)
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageError::Io(ref err) => write!(f, "Could not access image file: {}", err),
+            ImageError::Encoding(ref err) => write!(f, "Could not write PNG: {}", err),
+            ImageError::Decoding(ref err) => write!(f, "Could not read PNG: {}", err),
+            ImageError::Unsupported(ref message) => write!(f, "Unsupported PNG: {}", message)
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> Self {
+        ImageError::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for ImageError {
+    fn from(err: png::EncodingError) -> Self {
+        ImageError::Encoding(err)
+    }
+}
+
+impl From<png::DecodingError> for ImageError {
+    fn from(err: png::DecodingError) -> Self {
+        ImageError::Decoding(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    // Row by row from the top, four bytes per pixel
+    pixels: Vec<u8>
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, color: Vector4<f64>) -> Image {
+        let pixel = to_bytes(color);
+        Image {
+            width,
+            height,
+            pixels: (0..width * height).flat_map(|_| pixel.iter().cloned()).collect()
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = self.get_index(x, y);
+        [self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3]]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        let index = self.get_index(x, y);
+        self.pixels[index..index + 4].copy_from_slice(&pixel);
+    }
+
+    // Paints color over the pixel using its alpha
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: Vector4<f64>) {
+        let below = self.get_pixel(x, y);
+        let below = Vector4::new(below[0] as f64, below[1] as f64, below[2] as f64, below[3] as f64) / 255.0;
+        let alpha = color.w.clamp(0.0, 1.0);
+        let out_alpha = alpha + below.w * (1.0 - alpha);
+        let blended = if out_alpha > 0.0 {
+            (color * alpha + below * below.w * (1.0 - alpha)) / out_alpha
+        }
+        else {
+            Vector4::new(0.0, 0.0, 0.0, 0.0)
+        };
+        self.set_pixel(x, y, to_bytes(Vector4::new(blended.x, blended.y, blended.z, out_alpha)));
+    }
+
+    fn get_index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "Pixel ({}, {}) is outside of a {}x{} image", x, y, self.width, self.height);
+        ((y * self.width + x) * 4) as usize
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    // Reads 8-bit RGB or RGBA images, which covers anything save_png writes
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Image, ImageError> {
+        let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        if info.bit_depth != png::BitDepth::Eight {
+            return Err(ImageError::Unsupported(format!("bit depth {:?}", info.bit_depth)));
+        }
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer[..info.buffer_size()].to_vec(),
+            png::ColorType::Rgb => buffer[..info.buffer_size()]
+                .chunks(3)
+                .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            other => return Err(ImageError::Unsupported(format!("colour type {:?}", other)))
+        };
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels
+        })
+    }
+
+    pub fn compare(&self, reference: &Image, channel_tolerance: u8) -> ImageDiff {
+        if self.width != reference.width || self.height != reference.height {
+            return ImageDiff {
+                size_matches: false,
+                total_pixels: (self.width * self.height) as usize,
+                mismatched_pixels: (self.width * self.height) as usize,
+                max_difference: 255
+            };
+        }
+
+        let mut mismatched_pixels = 0;
+        let mut max_difference = 0;
+        for (pixel, reference_pixel) in self.pixels.chunks(4).zip(reference.pixels.chunks(4)) {
+            let difference = pixel
+                .iter()
+                .zip(reference_pixel)
+                .map(|(&a, &b)| a.abs_diff(b))
+                .max()
+                .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > channel_tolerance {
+                mismatched_pixels += 1;
+            }
+        }
+
+        ImageDiff {
+            size_matches: true,
+            total_pixels: (self.width * self.height) as usize,
+            mismatched_pixels,
+            max_difference
+        }
+    }
+
+    // Mismatched pixels in red over a faded copy of this image, for seeing where a comparison failed
+    pub fn get_difference_image(&self, reference: &Image, channel_tolerance: u8) -> Image {
+        let mut output = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.get_pixel(x, y);
+                let matches = x < reference.width && y < reference.height && {
+                    let reference_pixel = reference.get_pixel(x, y);
+                    pixel.iter().zip(reference_pixel.iter()).all(|(&a, &b)| (a as i16 - b as i16).abs() <= channel_tolerance as i16)
+                };
+                let faded = |channel: u8| channel / 4;
+                output.set_pixel(x, y, if matches { [faded(pixel[0]), faded(pixel[1]), faded(pixel[2]), 255] } else { [255, 0, 0, 255] });
+            }
+        }
+        output
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ImageDiff {
+    pub size_matches: bool,
+    pub total_pixels: usize,
+    // Pixels with a channel further than the tolerance from the reference
+    pub mismatched_pixels: usize,
+    pub max_difference: u8
+}
+
+impl ImageDiff {
+    // Allows a small share of pixels to differ, e.g. along the edges of shapes
+    pub fn passes(&self, max_mismatched_fraction: f64) -> bool {
+        self.size_matches && self.mismatched_pixels as f64 <= self.total_pixels as f64 * max_mismatched_fraction
+    }
+}
+
+impl fmt::Display for ImageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.size_matches {
+            return write!(f, "The image sizes differ");
+        }
+        write!(f, "{} of {} pixels differ, by at most {}", self.mismatched_pixels, self.total_pixels, self.max_difference)
+    }
+}
+
+fn to_bytes(color: Vector4<f64>) -> [u8; 4] {
+    let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color.x), channel(color.y), channel(color.z), channel(color.w)]
+}
@@ -2,34 +2,90 @@ pub mod tree;
 pub mod player;
 pub mod position;
 pub mod movable;
-use self::tree::{Tree, TreeBranch, TreeData, TreeBuilder, BranchId};
+pub mod physics;
+pub mod camera;
+pub mod headless;
+pub mod gamepad;
+pub mod replay;
+pub mod action_map;
+pub mod footprint;
+pub mod enemy;
+pub mod collision;
+pub mod pickup;
+pub mod setup;
+pub mod theme;
+pub mod svg_export;
+pub mod raster;
+pub mod image;
+pub mod rendering;
+use self::tree::{Tree, TreeBranch, TreeData};
 use self::player::Player;
-use self::position::Position;
 use self::movable::Movable;
+use self::physics::Physical;
+use self::camera::{Camera, CameraSettings};
+use self::replay::{Replay, ReplayFrame};
+use self::gamepad::{GamepadState, GamepadSettings, InputDevice};
+use self::action_map::{ActionMap, Action};
+use self::enemy::{Enemy, EnemyBehaviour};
+use self::collision::{Collidable, CollisionEvent, EntityId, detect_collisions};
+use self::pickup::{Pickup, ScoreRules};
+pub use self::setup::{GameSetup, Difficulty, SetupError};
+pub use self::theme::{Theme, ThemeError, BUILT_IN_THEMES};
+use self::svg_export::{SvgExport, SvgOptions};
+use self::raster::{RasterShape, RasterView, Rasterizer};
+use self::image::Image;
 use gg::debug::*;
 use gg::games::view_details::{ViewDetails, ViewDetails2D};
 use gg::games::GameInput;
-use gg::games::Game;
-use gg::rendering::{Renderable, BezierRect};
 use gg::input::{JoystickInput, KeyboardInput};
-use na::Vector2;
+use na::{Vector2, Vector4};
 use num::Zero;
+use std::path::{Path, PathBuf};
 
 pub struct TreeGame {
     pub input_keys: InputKeys,
+    previous_keys: InputKeys,
     pub setup: GameSetup,
     pub state: GameState,
     external_input: ExternalInput,
     pub tree: TreeData,
+    // A loaded level to play instead of generating the tree from the setup
+    level: Option<TreeData>,
     view_details: ViewDetails,
-    pub player: player::Player
+    pub camera: Camera,
+    pub recording: Option<Replay>,
+    recording_path: Option<PathBuf>,
+    pub gamepad_settings: GamepadSettings,
+    pub action_map: ActionMap,
+    pub player: player::Player,
+    pub enemies: Vec<Enemy>,
+    pub pickups: Vec<Pickup>,
+    pub score_rules: ScoreRules,
+    pub theme: Theme,
+    // Cycled through after the built in themes, and read again each time it comes round
+    theme_file: Option<PathBuf>,
+    // Position of the current theme in BUILT_IN_THEMES followed by the theme file
+    theme_index: usize,
+    pub collision_events: Vec<CollisionEvent>
 }
 
 impl TreeGame {
-    pub fn new(setup: GameSetup) -> TreeGame {
-        TreeGame {
+    pub fn new(setup: GameSetup) -> Result<TreeGame, SetupError> {
+        TreeGame::create(setup, None)
+    }
+
+    pub fn from_level(setup: GameSetup, level: TreeData) -> Result<TreeGame, SetupError> {
+        TreeGame::create(setup, Some(level))
+    }
+
+    fn create(setup: GameSetup, level: Option<TreeData>) -> Result<TreeGame, SetupError> {
+        setup.validate()?;
+        let tree = TreeGame::build_tree(&setup, &level);
+        setup.validate_start(&tree)?;
+        Ok(TreeGame {
             input_keys: InputKeys::default(),
-            setup: setup,
+            previous_keys: InputKeys::default(),
+            setup,
             state: Default::default(),
             external_input: Default::default(),
             view_details: ViewDetails::TwoDim(
@@ -38,103 +94,452 @@ impl TreeGame {
                     ..Default::default()
                 }
             ),
-            tree: TreeBuilder::new(4).build_tree(),
-            player: Player::new(Position::new(BranchId::new(0, 0), Vector2::new(0.25, 0.0)), 0.5, 0.1)
+            camera: Camera::new(CameraSettings::default(), Vector2::zero()),
+            recording: None,
+            recording_path: None,
+            gamepad_settings: GamepadSettings::default(),
+            action_map: ActionMap::default(),
+            player: TreeGame::new_player(&setup),
+            enemies: TreeGame::spawn_enemies(&setup, &tree),
+            pickups: TreeGame::spawn_pickups(&tree),
+            score_rules: ScoreRules::default(),
+            theme: Theme::default(),
+            theme_file: None,
+            theme_index: 0,
+            tree,
+            level,
+            collision_events: Vec::new()
+        })
+    }
+
+    fn build_tree(setup: &GameSetup, level: &Option<TreeData>) -> TreeData {
+        let tree = match *level {
+            Some(ref level) => level.clone(),
+            None => setup.build_tree()
+        };
+        for error in tree.validate() {
+            debug(&format!("Invalid Tree: {}", error));
         }
+        tree
     }
 
-    pub fn reset(&mut self) {
+    fn new_player(setup: &GameSetup) -> Player {
+        let mut player = Player::new(setup.player_start, setup.player_length, setup.player_height);
+        *player.get_body_mut() = setup.get_player_body();
+        player
     }
 
-    fn update_view_details(&mut self) {
+    fn spawn_enemies(setup: &GameSetup, tree: &TreeData) -> Vec<Enemy> {
+        let speed = setup.difficulty.get_enemy_speed_scale();
+        tree.get_enemies()
+            .iter()
+            .filter(|spawn| tree.try_get_branch(spawn.position.get_branch_id()).is_ok())
+            .enumerate()
+            .map(|(index, spawn)| {
+                let base_speed = match spawn.behaviour {
+                    EnemyBehaviour::Patrol => 0.5,
+                    EnemyBehaviour::Wander => 0.4,
+                    EnemyBehaviour::Chase => 0.3
+                };
+                Enemy::new(spawn.position, 0.3, 0.1, base_speed * speed, spawn.behaviour, index as u64 + 1)
+            })
+            .collect()
     }
-}
 
-impl Game for TreeGame {
-    fn init(&mut self) {
-        
+    fn spawn_pickups(tree: &TreeData) -> Vec<Pickup> {
+        tree.get_pickups()
+            .iter()
+            .filter(|spawn| tree.try_get_branch(spawn.position.get_branch_id()).is_ok())
+            .map(Pickup::from)
+            .collect()
     }
 
-    fn update_input(&mut self) {
-        self.input_keys.player_mov.x = (self.external_input.kbd.right as isize - (self.external_input.kbd.left as isize)) as f64;
-        self.input_keys.player_mov.y = (self.external_input.kbd.up as isize - (self.external_input.kbd.down as isize)) as f64;
+    fn update_enemies(&mut self, t_step: f64) {
+        let player_pos = self.player.get_position();
+        for enemy in &mut self.enemies {
+            if let Err(err) = enemy.update(&self.tree, player_pos, t_step) {
+                debug(&format!("Enemy could not move: {}", err));
+            }
+        }
     }
 
-    fn update_logic(&mut self, t_step: f64){
-        debug_clock_start("Logic::update_logic");
-        self.player.change_position(&self.tree, self.input_keys.player_mov * t_step);
-        debug_clock_stop("Logic::update_logic");
+    fn update_collisions(&mut self) {
+        let mut entities: Vec<(EntityId, &dyn Collidable)> = vec![(EntityId::Player, &self.player)];
+        for (index, enemy) in self.enemies.iter().enumerate() {
+            entities.push((EntityId::Enemy(index), enemy));
+        }
+        for (index, pickup) in self.pickups.iter().enumerate() {
+            entities.push((EntityId::Pickup(index), pickup));
+        }
+        self.collision_events = detect_collisions(&self.tree, &entities);
+
+        let mut collected: Vec<usize> = self.collision_events
+            .iter()
+            .filter_map(|event| match event.get_other(EntityId::Player) {
+                Some(EntityId::Pickup(index)) => Some(index),
+                _ => None
+            })
+            .collect();
+        // Remove from the back so the remaining indices stay valid
+        collected.sort();
+        collected.dedup();
+        for index in collected.into_iter().rev() {
+            let pickup = self.pickups.remove(index);
+            self.collect_pickup(&pickup);
+        }
+
+        let enemy_contact = self.collision_events
+            .iter()
+            .any(|event| matches!(event.get_other(EntityId::Player), Some(EntityId::Enemy(_))));
+        if enemy_contact {
+            self.kill_player();
+        }
+    }
+
+    fn collect_pickup(&mut self, pickup: &Pickup) {
+        self.state.combo = if self.state.combo_time_left > 0.0 {
+            (self.state.combo + 1).min(self.score_rules.max_combo)
+        }
+        else {
+            1
+        };
+        self.state.combo_time_left = self.score_rules.combo_window;
+        self.state.score += self.score_rules.get_points(pickup, self.state.combo);
+    }
+
+    fn update_combo(&mut self, t_step: f64) {
+        self.state.combo_time_left -= t_step;
+        if self.state.combo_time_left <= 0.0 {
+            self.state.combo_time_left = 0.0;
+            self.state.combo = 0;
+        }
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Replay::for_game(self));
+    }
+
+    pub fn take_recording(&mut self) -> Option<Replay> {
+        self.recording.take()
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        if let Some(index) = BUILT_IN_THEMES.iter().position(|&name| name == theme.name) {
+            self.theme_index = index;
+        }
+        self.theme = theme;
+    }
+
+    // Switches to the theme in the file and adds it to the themes next_theme cycles through
+    pub fn set_theme_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ThemeError> {
+        self.theme = Theme::load(&path)?;
+        self.theme_file = Some(path.as_ref().to_path_buf());
+        self.theme_index = BUILT_IN_THEMES.len();
+        Ok(())
     }
 
-    fn get_view(&self) -> ViewDetails {
-        self.view_details
+    pub fn next_theme(&mut self) {
+        let count = BUILT_IN_THEMES.len() + if self.theme_file.is_some() { 1 } else { 0 };
+        self.theme_index = (self.theme_index + 1) % count;
+        if let Some(theme) = BUILT_IN_THEMES.get(self.theme_index).and_then(|&name| Theme::built_in(name)) {
+            self.theme = theme;
+            return;
+        }
+        if let Some(ref path) = self.theme_file {
+            match Theme::load(path) {
+                Ok(theme) => {
+                    self.theme = theme;
+                    return;
+                },
+                Err(err) => debug(&format!("Skipping the theme file: {}", err))
+            }
+        }
+        self.theme_index = 0;
+        self.theme = Theme::classic();
     }
 
-    fn get_renderables(&self) -> Vec<Box<Renderable>> {
-        debug_clock_start("Render::get_renderables");
-        let mut output: Vec<Box<Renderable>> =
-            self.tree.get_branches()
-            .values()
-            .map(|br| -> Box<Renderable> { Box::new(BezierRect::from(br.get_visual())) })
+    // The tree with everything on it, coloured by the options' theme
+    pub fn get_svg_export(&self, options: SvgOptions) -> SvgExport<'_> {
+        let theme = options.theme.clone();
+        let mut export = SvgExport::new(&self.tree, options);
+        for pickup in &self.pickups {
+            export.add_collidable(pickup, pickup.get_color(&theme));
+        }
+        for enemy in &self.enemies {
+            export.add_collidable(enemy, theme.enemy);
+        }
+        export.add_collidable(&self.player, theme.player);
+        export
+    }
+
+    fn get_branch_color(&self, branch: &TreeBranch) -> Vector4<f64> {
+        self.theme.get_branch_color(&self.tree, branch, self.tree.get_max_depth())
+    }
+
+    // Everything get_renderables draws for the software rasterizer, with the branches sorted so
+    // snapshots come out the same every run
+    pub fn get_raster_shapes(&self) -> Vec<RasterShape> {
+        let mut branches: Vec<&TreeBranch> = self.tree.get_branches().values().collect();
+        branches.sort_by_key(|br| (br.get_id().layer, br.get_id().id));
+        let mut output: Vec<RasterShape> = branches
+            .into_iter()
+            .map(|br| RasterShape::Patch { visual: br.get_visual(), color: self.get_branch_color(br) })
             .collect();
 
-        let mut player_parts: Vec<Box<Renderable>> = self.player.get_render_parts(&self.tree);
-        output.append(&mut player_parts);
+        output.append(&mut self.player.get_raster_shapes(&self.tree, &self.theme));
 
-        debug_clock_stop("Render::get_renderables");
+        for pickup in &self.pickups {
+            output.append(&mut pickup.get_raster_shapes(&self.tree, &self.theme));
+        }
+
+        for enemy in &self.enemies {
+            output.append(&mut enemy.get_raster_shapes(&self.tree, &self.theme));
+        }
         output
     }
 
-    fn get_input<'a>(&'a mut self) -> Option<&'a mut GameInput> {
-         Some(&mut self.external_input)
+    // Renders what the camera sees without a GPU
+    pub fn snapshot(&self, width: u32, height: u32, view: RasterView) -> Image {
+        Rasterizer::new(width, height).render(&self.get_raster_shapes(), view)
+    }
+
+    // Records from now on and writes the replay out when the game is dropped
+    pub fn record_to<P: AsRef<Path>>(&mut self, path: P) {
+        self.start_recording();
+        self.recording_path = Some(path.as_ref().to_path_buf());
+    }
+
+    // Rebuilds the tree and respawns the player, ready to play again
+    pub fn reset(&mut self) {
+        self.tree = TreeGame::build_tree(&self.setup, &self.level);
+        self.player = TreeGame::new_player(&self.setup);
+        self.enemies = TreeGame::spawn_enemies(&self.setup, &self.tree);
+        self.pickups = TreeGame::spawn_pickups(&self.tree);
+        self.state = GameState::new();
+        self.state.phase = GamePhase::Playing;
+
+        let player_pos = self.get_player_world_position();
+        let layer = self.player.get_position().get_branch_id().layer;
+        self.camera.snap_to(player_pos, layer);
+    }
+
+    // Skips the title screen, e.g. for headless runs
+    pub fn start(&mut self) {
+        self.state.phase = GamePhase::Playing;
+    }
+
+    pub fn kill_player(&mut self) {
+        self.state.player_death = true;
+    }
+
+    fn update_phase(&mut self) {
+        let pressed = |now: bool, before: bool| now && !before;
+        let confirm = pressed(self.input_keys.jump_branch, self.previous_keys.jump_branch)
+            || pressed(self.input_keys.interact, self.previous_keys.interact);
+        let pause = pressed(self.input_keys.pause, self.previous_keys.pause);
+
+        self.state.phase = match self.state.phase {
+            GamePhase::Title if confirm => GamePhase::Playing,
+            GamePhase::Playing if self.state.player_death => GamePhase::GameOver,
+            GamePhase::Playing if pause => GamePhase::Paused,
+            GamePhase::Paused if pause => GamePhase::Playing,
+            GamePhase::GameOver if confirm => GamePhase::Restart,
+            phase => phase
+        };
+
+        if self.state.phase == GamePhase::Restart {
+            self.reset();
+        }
+    }
+
+    fn get_player_world_position(&self) -> Vector2<f64> {
+        self.tree.get_world_position(self.player.get_position())
+    }
+
+    fn update_view_details(&mut self, t_step: f64) {
+        let player_pos = self.get_player_world_position();
+        let layer = self.player.get_position().get_branch_id().layer;
+        self.camera.update(player_pos, layer, t_step);
+
+        self.view_details = ViewDetails::TwoDim(
+            ViewDetails2D {
+                camera_pos: self.camera.get_pos(),
+                viewport_height: self.camera.get_viewport_height(),
+                viewport_length: self.camera.get_viewport_height(),
+                use_aspect_ratio: false
+            }
+        );
+    }
+}
+
+// The per-frame steps generic_game drives through the Game trait, callable without a renderer
+impl TreeGame {
+    pub fn init(&mut self) {
+        let player_pos = self.get_player_world_position();
+        let layer = self.player.get_position().get_branch_id().layer;
+        self.camera.snap_to(player_pos, layer);
+        self.update_view_details(0.0);
+    }
+
+    pub fn update_input(&mut self) {
+        let kbd = &self.external_input.kbd;
+        let mut gamepad = GamepadState::from(&self.external_input.gamepad);
+        gamepad.stick = self.gamepad_settings.stick.apply(gamepad.stick);
+        let action_map = &self.action_map;
+        let value = |action: Action, keyboard: bool| action_map.get_value(action, kbd, &gamepad, keyboard);
+
+        // Whichever device was used last stays in control until the other is touched
+        let kbd_used = Action::ALL.iter().any(|&action| value(action, true) > 0.0);
+        let gamepad_used = Action::ALL.iter().any(|&action| value(action, false) > 0.0);
+        if kbd_used && !gamepad_used {
+            self.input_keys.active_device = InputDevice::Keyboard;
+        }
+        else if gamepad_used && !kbd_used {
+            self.input_keys.active_device = InputDevice::Gamepad;
+        }
+
+        let keyboard = self.input_keys.active_device == InputDevice::Keyboard;
+        self.input_keys.player_mov = Vector2::new(
+            value(Action::MoveRight, keyboard) - value(Action::MoveLeft, keyboard),
+            value(Action::MoveUp, keyboard) - value(Action::MoveDown, keyboard)
+        );
+        self.input_keys.jump_branch = value(Action::JumpBranch, keyboard) > 0.0;
+        self.input_keys.interact = value(Action::Interact, keyboard) > 0.0;
+        self.input_keys.pause = value(Action::Pause, keyboard) > 0.0;
+        self.input_keys.next_theme = value(Action::NextTheme, keyboard) > 0.0;
+    }
+
+    pub fn update_logic(&mut self, t_step: f64){
+        debug_clock_start("Logic::update_logic");
+        if let Some(ref mut recording) = self.recording {
+            if recording.frames.is_empty() {
+                recording.start_phase = self.state.phase;
+            }
+        }
+        self.update_phase();
+        if self.input_keys.next_theme && !self.previous_keys.next_theme {
+            self.next_theme();
+        }
+        if self.state.phase == GamePhase::Playing {
+            self.state.survival_time += t_step;
+            self.update_combo(t_step);
+            if let Err(err) = self.player.update_physics(&self.tree, self.input_keys.player_mov, t_step) {
+                debug(&format!("Player could not move, returning them to the start: {}", err));
+                self.player.set_position(self.setup.player_start);
+                self.player.get_body_mut().velocity = Vector2::zero();
+            }
+            self.update_enemies(t_step);
+            self.update_collisions();
+            if self.state.player_death {
+                self.state.phase = GamePhase::GameOver;
+            }
+        }
+        if let Some(ref mut recording) = self.recording {
+            recording.frames.push(ReplayFrame::capture(&self.external_input, t_step, self.player.get_position()));
+        }
+        self.update_view_details(t_step);
+        self.previous_keys = self.input_keys;
+        debug_clock_stop("Logic::update_logic");
+    }
+}
+
+impl Drop for TreeGame {
+    fn drop(&mut self) {
+        if let (Some(path), Some(recording)) = (self.recording_path.take(), self.recording.take()) {
+            if let Err(err) = recording.save(&path) {
+                debug(&format!("Could not save the replay to {}: {}", path.display(), err));
+            }
+        }
     }
 }
 
 #[derive(Copy, Clone)]
 pub struct InputKeys{
-    player_mov: Vector2<f64>
+    player_mov: Vector2<f64>,
+    pub jump_branch: bool,
+    pub interact: bool,
+    pub pause: bool,
+    pub next_theme: bool,
+    pub active_device: InputDevice
 }
 
 impl Default for InputKeys {
     fn default() -> Self {
         InputKeys {
-            player_mov: Vector2::zero()
+            player_mov: Vector2::zero(),
+            jump_branch: false,
+            interact: false,
+            pause: false,
+            next_theme: false,
+            active_device: InputDevice::Keyboard
         }
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct GameSetup{
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum GamePhase {
+    #[default]
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    Restart
 }
 
-impl Default for GameSetup {
-    fn default() -> Self {
-        GameSetup {
+impl GamePhase {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GamePhase::Title => "title",
+            GamePhase::Playing => "playing",
+            GamePhase::Paused => "paused",
+            GamePhase::GameOver => "game_over",
+            GamePhase::Restart => "restart"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GamePhase> {
+        match name {
+            "title" => Some(GamePhase::Title),
+            "playing" => Some(GamePhase::Playing),
+            "paused" => Some(GamePhase::Paused),
+            "game_over" => Some(GamePhase::GameOver),
+            "restart" => Some(GamePhase::Restart),
+            _ => None
         }
     }
 }
 
-#[derive(Copy, Clone, Default)]
+
+#[derive(Copy, Clone, Debug, Default)]
 pub struct GameState{
+    pub phase: GamePhase,
     pub player_death: bool,
     pub survival_time: f64,
+    pub score: u64,
+    // Pickups collected in a row, each within the combo window of the last
+    pub combo: u32,
+    pub combo_time_left: f64,
 }
 
 impl GameState {
     pub fn new() -> GameState{
-        GameState{ player_death: false,
+        GameState{ phase: GamePhase::Title,
+                   player_death: false,
                    survival_time: 0.0,
+                   score: 0,
+                   combo: 0,
+                   combo_time_left: 0.0,
         }
     }
 }
 
 #[derive(Clone, Default)]
-struct ExternalInput {
-    kbd: KeyboardInput,
-    gamepad: JoystickInput
+pub struct ExternalInput {
+    pub kbd: KeyboardInput,
+    pub gamepad: JoystickInput
 }
 
 impl GameInput for ExternalInput {
-    fn get_kbd_inp<'a>(&'a mut self) -> Option<&'a mut KeyboardInput> { Some(&mut self.kbd) }
-    fn get_joystick_inp<'a>(&'a mut self) -> Option<&'a mut JoystickInput> { Some(&mut self.gamepad) }
+    fn get_kbd_inp(&mut self) -> Option<&mut KeyboardInput> { Some(&mut self.kbd) }
+    fn get_joystick_inp(&mut self) -> Option<&mut JoystickInput> { Some(&mut self.gamepad) }
 }
@@ -1,74 +1,105 @@
-use super::tree::{Tree};
+use super::tree::{Tree, TreeError, BranchSide};
 use super::position::Position;
 use na::Vector2;
+use gg::geometry::Interval;
 use gg::debug::*;
 //use std;
 
+// Stops a degenerate tree (e.g. zero length branches) from looping forever
+pub const MAX_BRANCH_CROSSINGS: usize = 256;
+
+#[derive(Copy, Clone, Debug)]
+pub struct MoveReport {
+    pub branches_crossed: usize,
+    // Maps vertical logical lengths in the starting branch onto the branch the object finished in
+    pub vertical_scale: f64,
+    // The object ran into the end of a branch with no connection, or crossed too many branches in one move
+    pub blocked: bool,
+    // The object was stopped by the top or bottom wall of a branch
+    pub hit_wall: bool
+}
+
 pub trait Movable {
     fn get_position(&self) -> Position;
 
-    fn set_position(&mut self, Position);
-
-    fn change_position(&mut self, tree: &Tree, change_vec: Vector2<f64>) {
-        let current_position = self.get_position();
-
-        let current_branch = tree.get_branches()
-            .get(&current_position.get_branch_id())
-            .expect("Movable Object Not on a Valid Branch!");
-
-        let new_logical_pos = current_branch.get_new_logical_position(current_position.get_branch_position(), change_vec);
-
-        if new_logical_pos.x < 0.0 {
-            if let Some(connection) = current_branch.get_connection(new_logical_pos.y, super::tree::BranchSide::Left) {
-                let matching_connection = tree.get_matching_connection(current_branch.get_id(), connection);
-                let new_branch_id = connection.get_branch_id();
-                let new_branch = tree.get_branch(new_branch_id);
-                
-                let new_branch_boundary_interval = new_branch.get_logical_boundary_interval(matching_connection.get_boundary());
-                let old_branch_boundary_interval = current_branch.get_logical_boundary_interval(connection.get_boundary());
-                let new_branch_boundary_pos = old_branch_boundary_interval.fit_point_to(new_logical_pos.y, new_branch_boundary_interval);
-                let new_position = Position::new(new_branch_id, Vector2::new(new_branch.get_logical().length, new_branch_boundary_pos));
-
-                self.set_position(new_position);
-                // debug(&format!("Player Jumped to the left: Old Position: Branch:{:?} x:{:?} y:{:?}, \n New Position: Branch:{:?} x:{:?} y:{:?}, \n Jump: x:{:?} y:{:?}",
-                //                current_position.get_branch_id(), current_position.get_branch_position().x, current_position.get_branch_position().y,
-                //                new_position.get_branch_id(), new_position.get_branch_position().x, new_position.get_branch_position().y,
-                //                change_vec.x, change_vec.y));
+    fn set_position(&mut self, position: Position);
+
+    // How far the object extends above and below its position, used to keep it inside the walls
+    fn get_half_height(&self) -> f64 {
+        0.0
+    }
+
+    // Moves the object, carrying any motion left over after crossing a boundary on into the next branch
+    fn change_position(&mut self, tree: &dyn Tree, change_vec: Vector2<f64>) -> Result<MoveReport, TreeError> {
+        let mut current_position = self.get_position();
+        let mut remaining_change = change_vec;
+        let mut report = MoveReport {
+            branches_crossed: 0,
+            vertical_scale: 1.0,
+            blocked: false,
+            hit_wall: false
+        };
+
+        for _ in 0..MAX_BRANCH_CROSSINGS {
+            let current_branch = tree.try_get_branch(current_position.get_branch_id())?;
+            let current_logical = current_branch.get_logical();
+
+            let mut new_logical_pos = current_branch.get_new_logical_position(current_position.get_branch_position(), remaining_change);
+
+            let wall_x = new_logical_pos.x.max(0.0).min(current_logical.length);
+            let wall_y = (current_logical.get_half_width(wall_x) - self.get_half_height()).max(0.0);
+            if new_logical_pos.y.abs() > wall_y {
+                new_logical_pos.y = wall_y.copysign(new_logical_pos.y);
+                report.hit_wall = true;
             }
-            else {
-                let new_position = Position::new(current_position.get_branch_id(), Vector2::new(0.0, new_logical_pos.y));
-                self.set_position(new_position);
+
+            let (side, overshoot) = if new_logical_pos.x < 0.0 {
+                (BranchSide::Left, new_logical_pos.x)
             }
-        }
-        else if new_logical_pos.x > current_branch.get_logical().length {
-            if let Some(connection) = current_branch.get_connection(new_logical_pos.y, super::tree::BranchSide::Right) {
-                let matching_connection = tree.get_matching_connection(current_branch.get_id(), connection);
-                let new_branch_id = connection.get_branch_id();
-                let new_branch = tree.get_branch(new_branch_id);
-                
-                let new_branch_boundary_interval = new_branch.get_logical_boundary_interval(matching_connection.get_boundary());
-                let old_branch_boundary_interval = current_branch.get_logical_boundary_interval(connection.get_boundary());
-                let new_branch_boundary_pos = old_branch_boundary_interval.fit_point_to(new_logical_pos.y, new_branch_boundary_interval);
-                let new_position = Position::new(new_branch_id, Vector2::new(0.0, new_branch_boundary_pos));
-                self.set_position(new_position);
-                // debug(&format!("Player Jumped to the right: Old Position: Branch:{:?} x:{:?} y:{:?}, \n New Position: Branch:{:?} x:{:?} y:{:?}, \n Jump: x:{:?} y:{:?}",
-                //                current_position.get_branch_id(), current_position.get_branch_position().x, current_position.get_branch_position().y,
-                //                new_position.get_branch_id(), new_position.get_branch_position().x, new_position.get_branch_position().y,
-                //                change_vec.x, change_vec.y));
+            else if new_logical_pos.x > current_logical.length {
+                (BranchSide::Right, new_logical_pos.x - current_logical.length)
             }
             else {
-                let new_position = Position::new(current_position.get_branch_id(), Vector2::new(current_branch.get_logical().length, new_logical_pos.y));
-                self.set_position(new_position);
-                // debug(&format!("Player Stopped at the right: Old Position: Branch:{:?} x:{:?} y:{:?}, \n New Position: Branch:{:?} x:{:?} y:{:?}, \n Jump: x:{:?} y:{:?} \n connections: {:?}",
-                //                current_position.get_branch_id(), current_position.get_branch_position().x, current_position.get_branch_position().y,
-                //                new_position.get_branch_id(), new_position.get_branch_position().x, new_position.get_branch_position().y,
-                //                change_vec.x, change_vec.y,
-                //                current_branch.get_connections()));
+                self.set_position(Position::new(current_position.get_branch_id(), new_logical_pos));
+                return Ok(report);
+            };
+
+            let boundary_pos = current_logical.shift_along_tracking_line(new_logical_pos, -overshoot);
+
+            match current_branch.try_get_connection(boundary_pos.y, side)? {
+                Some(connection) => {
+                    let matching_connection = tree.try_get_matching_connection(current_branch.get_id(), connection)?;
+                    let new_branch_id = connection.get_branch_id();
+                    let new_branch = tree.try_get_branch(new_branch_id)?;
+
+                    let new_branch_boundary_interval = new_branch.get_logical_boundary_interval(matching_connection.get_boundary());
+                    let old_branch_boundary_interval = current_branch.get_logical_boundary_interval(connection.get_boundary());
+                    let new_branch_boundary_pos = old_branch_boundary_interval.fit_point_to(boundary_pos.y, new_branch_boundary_interval);
+                    let new_x_pos = match side {
+                        BranchSide::Left => new_branch.get_logical().length,
+                        BranchSide::Right => 0.0
+                    };
+
+                    current_position = Position::new(new_branch_id, Vector2::new(new_x_pos, new_branch_boundary_pos));
+                    remaining_change = Vector2::new(overshoot, 0.0);
+                    report.branches_crossed += 1;
+                    report.vertical_scale *= interval_length(new_branch_boundary_interval) / interval_length(old_branch_boundary_interval);
+                }
+                None => {
+                    self.set_position(Position::new(current_position.get_branch_id(), boundary_pos));
+                    report.blocked = true;
+                    return Ok(report);
+                }
             }
         }
-        else {
-            let new_position = Position::new(current_position.get_branch_id(), new_logical_pos);
-            self.set_position(new_position);
-        }
+
+        debug(&format!("Movable object crossed more than {} branches in one step, stopping at {:?}", MAX_BRANCH_CROSSINGS, current_position));
+        self.set_position(current_position);
+        report.blocked = true;
+        Ok(report)
     }
 }
+
+fn interval_length(interval: Interval) -> f64 {
+    interval.get_end() - interval.get_start()
+}
@@ -0,0 +1,94 @@
+use super::movable::{Movable, MoveReport};
+use super::tree::{Tree, TreeError};
+use na::Vector2;
+use num::Zero;
+
+#[derive(Copy, Clone, Debug)]
+pub enum WallResponse {
+    Slide,
+    // Reverses the vertical velocity, keeping this fraction of its speed
+    Bounce(f64)
+}
+
+// Velocities are in the logical frame of the branch the object is currently on
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicsBody {
+    pub velocity: Vector2<f64>,
+    pub acceleration: f64,
+    pub max_speed: f64,
+    // Fraction of velocity lost per second along an axis with no input
+    pub friction: f64,
+    pub wall_response: WallResponse
+}
+
+impl PhysicsBody {
+    pub fn new(acceleration: f64, max_speed: f64, friction: f64) -> PhysicsBody {
+        PhysicsBody {
+            velocity: Vector2::zero(),
+            acceleration,
+            max_speed,
+            friction,
+            wall_response: WallResponse::Slide
+        }
+    }
+
+    // Knockback may push the body beyond its max speed, friction then slows it back down
+    pub fn apply_knockback(&mut self, impulse: Vector2<f64>) {
+        self.velocity += impulse;
+    }
+
+    // Updates the velocity from the input direction and returns the displacement for this step
+    pub fn step(&mut self, input: Vector2<f64>, t_step: f64) -> Vector2<f64> {
+        let old_speed = self.velocity.norm();
+        self.velocity += input * self.acceleration * t_step;
+
+        let decay = (1.0 - self.friction * t_step).max(0.0);
+        if input.x == 0.0 {
+            self.velocity.x *= decay;
+        }
+        if input.y == 0.0 {
+            self.velocity.y *= decay;
+        }
+
+        let speed_limit = self.max_speed.max(old_speed);
+        let speed = self.velocity.norm();
+        if speed > speed_limit {
+            self.velocity *= speed_limit / speed;
+        }
+
+        self.velocity * t_step
+    }
+
+    // Keeps the velocity consistent with the frame of the branch the object has moved into
+    pub fn apply_move_report(&mut self, report: MoveReport) {
+        self.velocity.y *= report.vertical_scale;
+        if report.blocked {
+            self.velocity.x = 0.0;
+        }
+        if report.hit_wall {
+            self.velocity.y = match self.wall_response {
+                WallResponse::Slide => 0.0,
+                WallResponse::Bounce(restitution) => -self.velocity.y * restitution
+            };
+        }
+    }
+}
+
+impl Default for PhysicsBody {
+    fn default() -> Self {
+        PhysicsBody::new(8.0, 1.0, 8.0)
+    }
+}
+
+pub trait Physical: Movable {
+    fn get_body(&self) -> &PhysicsBody;
+
+    fn get_body_mut(&mut self) -> &mut PhysicsBody;
+
+    fn update_physics(&mut self, tree: &dyn Tree, input: Vector2<f64>, t_step: f64) -> Result<(), TreeError> {
+        let displacement = self.get_body_mut().step(input, t_step);
+        let report = self.change_position(tree, displacement)?;
+        self.get_body_mut().apply_move_report(report);
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use super::position::Position;
+use super::tree::{Tree, BranchObject, PickupSpawn, PickupKind};
+use super::movable::Movable;
+use super::footprint::get_footprint;
+use super::collision::Collidable;
+use super::theme::Theme;
+use super::raster::RasterShape;
+use na::Vector4;
+
+const PICKUP_SIZE: f64 = 0.1;
+
+pub struct Pickup {
+    pos: Position,
+    kind: PickupKind
+}
+
+impl Pickup {
+    pub fn new(pos: Position, kind: PickupKind) -> Pickup {
+        Pickup {
+            pos,
+            kind
+        }
+    }
+
+    pub fn get_kind(&self) -> PickupKind {
+        self.kind
+    }
+
+    // Points before the layer and combo multipliers are applied
+    pub fn get_base_points(&self) -> u64 {
+        match self.kind {
+            PickupKind::Fruit => 100,
+            PickupKind::Leaf => 25
+        }
+    }
+
+    pub fn get_color(&self, theme: &Theme) -> Vector4<f64> {
+        match self.kind {
+            PickupKind::Fruit => theme.fruit,
+            PickupKind::Leaf => theme.leaf
+        }
+    }
+
+    pub fn get_branch_objects(&self, tree: &dyn Tree, theme: &Theme) -> Vec<Box<dyn BranchObject>> {
+        get_footprint(tree, self.pos, PICKUP_SIZE, PICKUP_SIZE, self.get_color(theme))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|part| -> Box<dyn BranchObject> { Box::new(part) })
+            .collect()
+    }
+
+    pub fn get_raster_shapes<T: Tree> (&self, tree: &T, theme: &Theme) -> Vec<RasterShape> {
+        self.get_branch_objects(tree, theme)
+            .into_iter()
+            .flat_map(|p| {p.get_raster_shapes(tree)})
+            .collect()
+    }
+}
+
+impl From<&PickupSpawn> for Pickup {
+    fn from(spawn: &PickupSpawn) -> Pickup {
+        Pickup::new(spawn.position, spawn.kind)
+    }
+}
+
+impl Movable for Pickup {
+    fn get_position(&self) -> Position {
+        self.pos
+    }
+
+    fn set_position(&mut self, new_pos: Position) {
+        self.pos = new_pos;
+    }
+
+    fn get_half_height(&self) -> f64 {
+        PICKUP_SIZE / 2.0
+    }
+}
+
+impl Collidable for Pickup {
+    fn get_length(&self) -> f64 {
+        PICKUP_SIZE
+    }
+
+    fn get_height(&self) -> f64 {
+        PICKUP_SIZE
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ScoreRules {
+    // Seconds after a pickup during which the next one extends the combo
+    pub combo_window: f64,
+    pub max_combo: u32,
+    // Extra multiplier gained for each layer further out the pickup sits
+    pub layer_bonus: f64
+}
+
+impl ScoreRules {
+    pub fn get_points(&self, pickup: &Pickup, combo: u32) -> u64 {
+        let layer = pickup.get_position().get_branch_id().layer as f64;
+        let combo = combo.max(1).min(self.max_combo) as f64;
+        (pickup.get_base_points() as f64 * (1.0 + layer * self.layer_bonus) * combo).round() as u64
+    }
+}
+
+impl Default for ScoreRules {
+    fn default() -> Self {
+        ScoreRules {
+            combo_window: 2.0,
+            max_combo: 5,
+            layer_bonus: 1.0
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Draws the game on the CPU, so snapshots can be taken on machines without a GPU. The same shapes
+// are turned into BezierRect and BezierSubrect renderables for the GPU renderer (see rendering).
+use super::tree::{Tree, TreeData, BoundingBox};
+use super::tree::tree_branch::{VisualSpec, LogicalSpec};
+use super::camera::Camera;
+use super::image::Image;
+use na::{Vector2, Vector4};
+
+#[derive(Clone)]
+pub enum RasterShape {
+    // A whole branch, as drawn by BezierRect
+    Patch { visual: VisualSpec, color: Vector4<f64> },
+    // A rectangle in a branch's logical coordinates, as drawn by BezierSubrect
+    Subrect {
+        visual: VisualSpec,
+        logical: LogicalSpec,
+        length: f64,
+        height: f64,
+        pos: Vector2<f64>,
+        color: Vector4<f64>
+    }
+}
+
+impl RasterShape {
+    pub fn get_color(&self) -> Vector4<f64> {
+        match *self {
+            RasterShape::Patch { color, .. } => color,
+            RasterShape::Subrect { color, .. } => color
+        }
+    }
+
+    pub fn get_bounds(&self) -> BoundingBox {
+        match *self {
+            RasterShape::Patch { ref visual, .. } => BoundingBox::from_visual(visual),
+            RasterShape::Subrect { ref visual, .. } => BoundingBox::from_visual(visual)
+        }
+    }
+
+    pub fn contains(&self, world_pos: Vector2<f64>) -> bool {
+        match *self {
+            RasterShape::Patch { ref visual, .. } => visual.get_patch_coords(world_pos).is_some(),
+            RasterShape::Subrect { ref visual, ref logical, length, height, pos, .. } => {
+                match visual.get_patch_coords(world_pos) {
+                    Some((t, v)) => {
+                        let x = t * logical.length;
+                        let y = v * logical.get_half_width(x);
+                        (x - pos.x).abs() <= length / 2.0 && (y - pos.y).abs() <= height / 2.0
+                    },
+                    None => false
+                }
+            }
+        }
+    }
+}
+
+// The part of the world a snapshot shows; its width follows from the image's aspect ratio
+#[derive(Copy, Clone, Debug)]
+pub struct RasterView {
+    pub center: Vector2<f64>,
+    pub height: f64
+}
+
+impl RasterView {
+    pub fn new(center: Vector2<f64>, height: f64) -> RasterView {
+        RasterView {
+            center,
+            height
+        }
+    }
+
+    pub fn from_camera(camera: &Camera) -> RasterView {
+        RasterView::new(camera.get_pos(), camera.get_viewport_height())
+    }
+
+    // Frames the whole tree with a little room to spare at the given aspect ratio (width / height)
+    pub fn fit_tree(tree: &TreeData, aspect_ratio: f64) -> RasterView {
+        let mut branches = tree.get_branches().values();
+        let mut bounds = match branches.next() {
+            Some(branch) => BoundingBox::from_branch(branch),
+            None => return RasterView::new(Vector2::new(0.0, 0.0), 1.0)
+        };
+        for branch in branches {
+            let branch_bounds = BoundingBox::from_branch(branch);
+            bounds.include(branch_bounds.min);
+            bounds.include(branch_bounds.max);
+        }
+        let size = bounds.max - bounds.min;
+        let height = size.y.max(size.x / aspect_ratio) * 1.05;
+        RasterView::new((bounds.min + bounds.max) / 2.0, height)
+    }
+}
+
+pub struct Rasterizer {
+    pub width: u32,
+    pub height: u32,
+    pub background: Vector4<f64>
+}
+
+impl Rasterizer {
+    pub fn new(width: u32, height: u32) -> Rasterizer {
+        Rasterizer {
+            width,
+            height,
+            background: Vector4::new(0.0, 0.0, 0.0, 1.0)
+        }
+    }
+
+    // Shapes are painted in order, each blended over the ones before it
+    pub fn render(&self, shapes: &[RasterShape], view: RasterView) -> Image {
+        let mut image = Image::new(self.width, self.height, self.background);
+        let pixel_size = view.height / self.height as f64;
+
+        for shape in shapes {
+            let bounds = shape.get_bounds();
+            let (min_x, max_y) = self.to_pixel(bounds.min, view, pixel_size);
+            let (max_x, min_y) = self.to_pixel(bounds.max, view, pixel_size);
+            let x_range = clamp_range(min_x, max_x, self.width);
+            let y_range = clamp_range(min_y, max_y, self.height);
+            let color = shape.get_color();
+
+            for y in y_range.0..y_range.1 {
+                for x in x_range.0..x_range.1 {
+                    if shape.contains(self.to_world(x, y, view, pixel_size)) {
+                        image.blend_pixel(x, y, color);
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    // Samples at the pixel's centre, with y running down the image
+    fn to_world(&self, x: u32, y: u32, view: RasterView, pixel_size: f64) -> Vector2<f64> {
+        Vector2::new(
+            view.center.x + (x as f64 + 0.5 - self.width as f64 / 2.0) * pixel_size,
+            view.center.y - (y as f64 + 0.5 - self.height as f64 / 2.0) * pixel_size
+        )
+    }
+
+    fn to_pixel(&self, world: Vector2<f64>, view: RasterView, pixel_size: f64) -> (f64, f64) {
+        (
+            (world.x - view.center.x) / pixel_size + self.width as f64 / 2.0,
+            (view.center.y - world.y) / pixel_size + self.height as f64 / 2.0
+        )
+    }
+}
+
+fn clamp_range(min: f64, max: f64, size: u32) -> (u32, u32) {
+    let clamp = |value: f64| value.max(0.0).min(size as f64) as u32;
+    (clamp(min.floor()), clamp(max.ceil() + 1.0))
+}
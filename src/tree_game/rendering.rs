@@ -0,0 +1,134 @@
+// Hands the game to generic_game's window and renderer. This is the only module in tree_game that
+// uses gg::rendering: everything else describes what to draw as raster shapes, which are turned
+// into renderables here, so the game can be stepped and drawn (see raster) without a GPU.
+use super::TreeGame;
+use super::player::Player;
+use super::enemy::Enemy;
+use super::pickup::Pickup;
+use super::tree::Tree;
+use super::tree::tree_branch::{VisualSpec, LogicalSpec};
+use super::theme::Theme;
+use super::raster::RasterShape;
+use gg::debug::*;
+use gg::games::view_details::ViewDetails;
+use gg::games::{Game, GameInput};
+use gg::rendering::{Renderable, BezierRect, BezierLogic, BezierSubrect};
+
+impl Game for TreeGame {
+    fn init(&mut self) {
+        TreeGame::init(self);
+    }
+
+    fn update_input(&mut self) {
+        TreeGame::update_input(self);
+    }
+
+    fn update_logic(&mut self, t_step: f64) {
+        TreeGame::update_logic(self, t_step);
+    }
+
+    fn get_view(&self) -> ViewDetails {
+        self.view_details
+    }
+
+    fn get_renderables(&self) -> Vec<Box<dyn Renderable>> {
+        debug_clock_start("Render::get_renderables");
+        let mut output: Vec<Box<dyn Renderable>> =
+            self.tree.get_branches()
+            .values()
+            .map(|br| -> Box<dyn Renderable> {
+                let mut rect = BezierRect::from(br.get_visual());
+                rect.color = self.get_branch_color(br);
+                Box::new(rect)
+            })
+            .collect();
+
+        let mut player_parts: Vec<Box<dyn Renderable>> = self.player.get_render_parts(&self.tree, &self.theme);
+        output.append(&mut player_parts);
+
+        for pickup in &self.pickups {
+            output.append(&mut pickup.get_render_parts(&self.tree, &self.theme));
+        }
+
+        for enemy in &self.enemies {
+            output.append(&mut enemy.get_render_parts(&self.tree, &self.theme));
+        }
+
+        debug_clock_stop("Render::get_renderables");
+        output
+    }
+
+    fn get_input(&mut self) -> Option<&mut dyn GameInput> {
+         Some(&mut self.external_input)
+    }
+}
+
+impl Player {
+    pub fn get_render_parts<T: Tree> (&self, tree: &T, theme: &Theme) -> Vec<Box<dyn Renderable>> {
+        self.get_branch_objects(tree, theme)
+            .into_iter()
+            .flat_map(|p| {p.get_raster_shapes(tree)})
+            .map(get_renderable)
+            .collect()
+    }
+}
+
+impl Enemy {
+    pub fn get_render_parts<T: Tree> (&self, tree: &T, theme: &Theme) -> Vec<Box<dyn Renderable>> {
+        self.get_branch_objects(tree, theme)
+            .into_iter()
+            .flat_map(|p| {p.get_raster_shapes(tree)})
+            .map(get_renderable)
+            .collect()
+    }
+}
+
+impl Pickup {
+    pub fn get_render_parts<T: Tree> (&self, tree: &T, theme: &Theme) -> Vec<Box<dyn Renderable>> {
+        self.get_branch_objects(tree, theme)
+            .into_iter()
+            .flat_map(|p| {p.get_raster_shapes(tree)})
+            .map(get_renderable)
+            .collect()
+    }
+}
+
+fn get_renderable(shape: RasterShape) -> Box<dyn Renderable> {
+    match shape {
+        RasterShape::Patch { visual, color } => {
+            let mut rect = BezierRect::from(visual);
+            rect.color = color;
+            Box::new(rect)
+        },
+        RasterShape::Subrect { visual, logical, length, height, pos, color } => Box::new(BezierSubrect {
+            bezier: visual.into(),
+            logic: logical.into(),
+            length,
+            height,
+            sub_pos: pos,
+            color
+        })
+    }
+}
+
+impl From<VisualSpec> for BezierRect {
+    fn from (spec: VisualSpec) -> Self {
+        BezierRect {
+            control: spec.patch.control.into(),
+            vert_dir: spec.patch.vert_dir,
+            width: spec.patch.width,
+            pos: spec.patch.pos,
+            color: spec.color
+        }
+    }
+}
+
+impl From<LogicalSpec> for BezierLogic {
+    fn from (spec: LogicalSpec) -> Self {
+        BezierLogic {
+            length: spec.length,
+            width_left: spec.left_width,
+            width_right: spec.right_width
+        }
+    }
+}
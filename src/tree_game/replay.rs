@@ -0,0 +1,357 @@
+// Records the input and t_step of every frame, along with where the player ended up, so that
+// movement bugs can be reproduced exactly. A header describing the game the replay was recorded
+// in comes first, then one frame per line:
+//
+//   phase <title|playing|paused|game_over|restart>
+//   setup <setup file line>
+//   tree <fingerprint in hex>
+//   binding <bindings file line>
+//   stick <deadzone> <response_exponent>
+//   frame <t_step> <keys...> <stick_x> <stick_y> <a> <b> <x> <y> <start> <branch_id> <branch_layer> <x> <y>
+//
+// with keys (in the order of Key::ALL) and buttons written as 0 or 1. There is a setup line for
+// each line of the game's setup file and a binding line for each of its key and button bindings;
+// parts of the header that are missing are not checked, so replays without one play on any game.
+use super::{TreeGame, GamePhase, ExternalInput};
+use super::position::Position;
+use super::gamepad::{GamepadState, StickSettings, BUTTON_COUNT};
+use super::headless::HeadlessRunner;
+use super::tree::BranchId;
+use super::action_map::{Key, KEY_COUNT};
+use na::Vector2;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const POSITION_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    // The game differs from the one the replay was recorded in
+    Mismatch(String)
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReplayError::Io(ref err) => write!(f, "Could not access replay file: {}", err),
+            ReplayError::Parse { line, ref message } => write!(f, "Replay file line {}: {}", line, message),
+            ReplayError::Mismatch(ref message) => write!(f, "Replay was recorded in a different game: {}", message)
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ReplayFrame {
+    pub t_step: f64,
+    // Indexed in the order of Key::ALL
+    pub keys: [bool; KEY_COUNT],
+    pub gamepad: GamepadState,
+    // Where the player was after this frame's update
+    pub position: Position
+}
+
+impl ReplayFrame {
+    pub fn capture(input: &ExternalInput, t_step: f64, position: Position) -> ReplayFrame {
+        let mut keys = [false; KEY_COUNT];
+        for (pressed, key) in keys.iter_mut().zip(Key::ALL.iter()) {
+            *pressed = key.is_pressed(&input.kbd);
+        }
+        ReplayFrame {
+            t_step,
+            keys,
+            gamepad: GamepadState::from(&input.gamepad),
+            position
+        }
+    }
+
+    pub fn get_input(&self) -> ExternalInput {
+        let mut input = ExternalInput::default();
+        for (&pressed, key) in self.keys.iter().zip(Key::ALL.iter()) {
+            key.set_pressed(&mut input.kbd, pressed);
+        }
+        self.gamepad.apply_to(&mut input.gamepad);
+        input
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Divergence {
+    pub frame: usize,
+    pub expected: Position,
+    pub actual: Position
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Replay diverged on frame {}: expected {:?} but found {:?}", self.frame, self.expected, self.actual)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayResult {
+    pub frames: usize,
+    pub final_position: Position,
+    // The first frame where the replayed position differed from the recording
+    pub divergence: Option<Divergence>
+}
+
+impl ReplayResult {
+    pub fn matches(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    // Replays without a phase line were recorded from the title screen
+    pub start_phase: GamePhase,
+    // Lines of the recorded game's setup file, empty when unknown
+    pub setup: Vec<String>,
+    pub tree_fingerprint: Option<u64>,
+    // Lines of the recorded game's bindings file, empty when unknown
+    pub bindings: Vec<String>,
+    pub stick: Option<StickSettings>,
+    pub frames: Vec<ReplayFrame>
+}
+
+impl Replay {
+    pub fn new() -> Replay {
+        Replay {
+            start_phase: GamePhase::Title,
+            setup: Vec::new(),
+            tree_fingerprint: None,
+            bindings: Vec::new(),
+            stick: None,
+            frames: Vec::new()
+        }
+    }
+
+    // An empty replay that can only be played back on a game like this one
+    pub fn for_game(game: &TreeGame) -> Replay {
+        Replay {
+            start_phase: game.state.phase,
+            setup: get_setup_lines(game),
+            tree_fingerprint: Some(game.tree.get_fingerprint()),
+            bindings: get_binding_lines(game),
+            stick: Some(game.gamepad_settings.stick),
+            frames: Vec::new()
+        }
+    }
+
+    // Checks the game has the setup, tree and input settings the replay was recorded with, where
+    // they are known
+    pub fn check_game(&self, game: &TreeGame) -> Result<(), ReplayError> {
+        check_lines("setup", &self.setup, &get_setup_lines(game))?;
+        check_lines("bindings", &self.bindings, &get_binding_lines(game))?;
+        if let Some(fingerprint) = self.tree_fingerprint {
+            if fingerprint != game.tree.get_fingerprint() {
+                return Err(ReplayError::Mismatch(format!("the tree is {:016x} instead of {:016x}", game.tree.get_fingerprint(), fingerprint)));
+            }
+        }
+        if let Some(stick) = self.stick {
+            let actual = game.gamepad_settings.stick;
+            if stick.deadzone != actual.deadzone || stick.response_exponent != actual.response_exponent {
+                return Err(ReplayError::Mismatch(format!(
+                    "the stick has deadzone {} and response {} instead of {} and {}",
+                    actual.deadzone, actual.response_exponent, stick.deadzone, stick.response_exponent
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Feeds the recorded input to the game and checks it follows the recorded trajectory
+    pub fn play(&self, game: TreeGame) -> Result<ReplayResult, ReplayError> {
+        self.check_game(&game)?;
+        let mut runner = HeadlessRunner::with_phase(game, 0.0, self.start_phase);
+        let mut divergence = None;
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            runner.step_input(&frame.get_input(), frame.t_step);
+            let actual = runner.get_report().final_position;
+            if divergence.is_none() && !positions_match(frame.position, actual) {
+                divergence = Some(Divergence {
+                    frame: index,
+                    expected: frame.position,
+                    actual
+                });
+            }
+        }
+
+        Ok(ReplayResult {
+            frames: self.frames.len(),
+            final_position: runner.get_report().final_position,
+            divergence
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_replay_string().as_bytes())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, ReplayError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        Replay::from_replay_str(&contents)
+    }
+
+    pub fn to_replay_string(&self) -> String {
+        let mut output = format!("phase {}\n", self.start_phase.name());
+        for line in &self.setup {
+            output += &format!("setup {}\n", line);
+        }
+        if let Some(fingerprint) = self.tree_fingerprint {
+            output += &format!("tree {:016x}\n", fingerprint);
+        }
+        for line in &self.bindings {
+            output += &format!("binding {}\n", line);
+        }
+        if let Some(stick) = self.stick {
+            output += &format!("stick {} {}\n", stick.deadzone, stick.response_exponent);
+        }
+        for frame in &self.frames {
+            let id = frame.position.get_branch_id();
+            let pos = frame.position.get_branch_position();
+            output += &format!(
+                "frame {} {} {} {} {} {} {} {} {}\n",
+                frame.t_step,
+                flags_to_string(&frame.keys),
+                frame.gamepad.stick.x,
+                frame.gamepad.stick.y,
+                flags_to_string(&frame.gamepad.buttons),
+                id.id,
+                id.layer,
+                pos.x,
+                pos.y
+            );
+        }
+        output
+    }
+
+    pub fn from_replay_str(contents: &str) -> Result<Replay, ReplayError> {
+        let mut replay = Replay::new();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parse_error = |message| ReplayError::Parse { line: index + 1, message };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[0] {
+                "phase" if words.len() == 2 => {
+                    replay.start_phase = GamePhase::from_name(words[1])
+                        .ok_or_else(|| parse_error(format!("Unknown phase '{}'", words[1])))?;
+                },
+                "phase" => return Err(parse_error(format!("Expected 1 value but found {}", words.len() - 1))),
+                "setup" if words.len() > 1 => replay.setup.push(words[1..].join(" ")),
+                "setup" => return Err(parse_error("Expected a setup file line".to_string())),
+                "tree" if words.len() == 2 => {
+                    let fingerprint = u64::from_str_radix(words[1], 16)
+                        .map_err(|_| parse_error(format!("'{}' is not a hex fingerprint", words[1])))?;
+                    replay.tree_fingerprint = Some(fingerprint);
+                },
+                "tree" => return Err(parse_error(format!("Expected 1 value but found {}", words.len() - 1))),
+                "binding" if words.len() > 1 => replay.bindings.push(words[1..].join(" ")),
+                "binding" => return Err(parse_error("Expected a bindings file line".to_string())),
+                "stick" if words.len() == 3 => {
+                    let number = |word: &str| word.parse::<f64>().map_err(|_| parse_error(format!("'{}' is not a number", word)));
+                    replay.stick = Some(StickSettings {
+                        deadzone: number(words[1])?,
+                        response_exponent: number(words[2])?
+                    });
+                },
+                "stick" => return Err(parse_error(format!("Expected 2 values but found {}", words.len() - 1))),
+                "frame" => replay.frames.push(parse_frame(&words).map_err(parse_error)?),
+                other => return Err(parse_error(format!("Unknown entry '{}'", other)))
+            }
+        }
+        Ok(replay)
+    }
+}
+
+fn parse_frame(words: &[&str]) -> Result<ReplayFrame, String> {
+    let stick_start = 2 + KEY_COUNT;
+    let buttons_start = stick_start + 2;
+    let position_start = buttons_start + BUTTON_COUNT;
+    let expected_words = position_start + 4;
+    if words.len() != expected_words {
+        return Err(format!("Expected {} values but found {}", expected_words - 1, words.len() - 1));
+    }
+
+    let number = |word: &str| word.parse::<f64>().map_err(|_| format!("'{}' is not a number", word));
+    let whole_number = |word: &str| word.parse::<usize>().map_err(|_| format!("'{}' is not a whole number", word));
+    let flag = |word: &str| match word {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(format!("'{}' is not 0 or 1", word))
+    };
+
+    let mut keys = [false; KEY_COUNT];
+    for (key, word) in keys.iter_mut().zip(&words[2..stick_start]) {
+        *key = flag(word)?;
+    }
+    let mut buttons = [false; BUTTON_COUNT];
+    for (button, word) in buttons.iter_mut().zip(&words[buttons_start..position_start]) {
+        *button = flag(word)?;
+    }
+    let position = &words[position_start..];
+
+    Ok(ReplayFrame {
+        t_step: number(words[1])?,
+        keys,
+        gamepad: GamepadState {
+            stick: Vector2::new(number(words[stick_start])?, number(words[stick_start + 1])?),
+            buttons
+        },
+        position: Position::new(
+            BranchId::new(whole_number(position[0])?, whole_number(position[1])?),
+            Vector2::new(number(position[2])?, number(position[3])?)
+        )
+    })
+}
+
+fn get_setup_lines(game: &TreeGame) -> Vec<String> {
+    game.setup.to_config_string().lines().map(|line| line.to_string()).collect()
+}
+
+fn get_binding_lines(game: &TreeGame) -> Vec<String> {
+    game.action_map.to_config_string().lines().map(|line| line.to_string()).collect()
+}
+
+// Compares the lines of a file in any order; nothing is checked when the recording has none
+fn check_lines(what: &str, recorded: &[String], actual: &[String]) -> Result<(), ReplayError> {
+    if recorded.is_empty() {
+        return Ok(());
+    }
+    if let Some(line) = recorded.iter().find(|line| !actual.contains(line)) {
+        return Err(ReplayError::Mismatch(format!("the {} does not have '{}'", what, line)));
+    }
+    if let Some(line) = actual.iter().find(|line| !recorded.contains(line)) {
+        return Err(ReplayError::Mismatch(format!("the {} has '{}', which the recording did not", what, line)));
+    }
+    Ok(())
+}
+
+fn flags_to_string(flags: &[bool]) -> String {
+    flags.iter()
+        .map(|&flag| if flag { "1" } else { "0" })
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn positions_match(expected: Position, actual: Position) -> bool {
+    expected.get_branch_id() == actual.get_branch_id()
+        && (expected.get_branch_position() - actual.get_branch_position()).norm() <= POSITION_TOLERANCE
+}
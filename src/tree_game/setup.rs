@@ -0,0 +1,305 @@
+// Tunable parameters for a game. Config files give one setting per line, and any setting left out
+// keeps its default, e.g.
+//
+//   layers 5
+//   seed 42
+//   player_start 0 0 0.25 0
+//   enemies 5
+//   difficulty hard
+use super::position::Position;
+use super::tree::{Tree, TreeBuilder, TreeData, RandomSpec, BranchId};
+use super::physics::PhysicsBody;
+use na::Vector2;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// Each layer at least doubles the number of branches: 16 layers of two way splits is around 200,000
+// of them, which takes about half a second to build, and deeper trees quickly become unplayable
+pub const MAX_LAYERS: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None
+        }
+    }
+
+    pub fn get_enemy_speed_scale(&self) -> f64 {
+        match *self {
+            Difficulty::Easy => 0.7,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.4
+        }
+    }
+
+    pub fn get_pickup_chance(&self) -> f64 {
+        match *self {
+            Difficulty::Easy => 0.2,
+            Difficulty::Normal => 0.15,
+            Difficulty::Hard => 0.1
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub enum SetupError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    Invalid(String)
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetupError::Io(ref err) => write!(f, "Could not access setup file: {}", err),
+            SetupError::Parse { line, ref message } => write!(f, "Setup file line {}: {}", line, message),
+            SetupError::Invalid(ref message) => write!(f, "Invalid setup: {}", message)
+        }
+    }
+}
+
+impl From<io::Error> for SetupError {
+    fn from(err: io::Error) -> Self {
+        SetupError::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GameSetup {
+    pub num_layers: usize,
+    pub vertical_fill: f64,
+    pub horizontal_fill: f64,
+    // A seed gives a randomly shaped tree, otherwise every branch splits in two
+    pub seed: Option<u64>,
+    pub player_start: Position,
+    pub player_length: f64,
+    pub player_height: f64,
+    pub player_acceleration: f64,
+    pub player_max_speed: f64,
+    pub player_friction: f64,
+    // Enemies placed on a generated tree; levels bring their own
+    pub enemy_count: usize,
+    pub difficulty: Difficulty
+}
+
+impl GameSetup {
+    pub fn build_tree(&self) -> TreeData {
+        let builder = match self.seed {
+            Some(seed) => TreeBuilder::new_random(self.num_layers, RandomSpec::new(seed)),
+            None => TreeBuilder::new(self.num_layers)
+        };
+        builder
+            .with_fills(self.vertical_fill, self.horizontal_fill)
+            .with_pickups(self.difficulty.get_pickup_chance(), self.seed.unwrap_or(0))
+            .with_enemies(self.enemy_count, self.seed.unwrap_or(0))
+            .build_tree()
+    }
+
+    pub fn get_player_body(&self) -> PhysicsBody {
+        PhysicsBody::new(self.player_acceleration, self.player_max_speed, self.player_friction)
+    }
+
+    pub fn validate(&self) -> Result<(), SetupError> {
+        let invalid = |message: String| Err(SetupError::Invalid(message));
+        if self.num_layers == 0 {
+            return invalid("the tree needs at least one layer".to_string());
+        }
+        if self.num_layers > MAX_LAYERS {
+            return invalid(format!("the tree can have at most {} layers but has {}", MAX_LAYERS, self.num_layers));
+        }
+        // The root trunk can fill the whole height, but a horizontal_fill of 1 leaves nothing for
+        // the layers after the first, so their branches have no length
+        if !(self.vertical_fill > 0.0 && self.vertical_fill <= 1.0) {
+            return invalid(format!("vertical_fill must be in (0, 1] but is {}", self.vertical_fill));
+        }
+        if !(self.horizontal_fill > 0.0 && self.horizontal_fill < 1.0) {
+            return invalid(format!("horizontal_fill must be in (0, 1) but is {}", self.horizontal_fill));
+        }
+        let sizes = [
+            ("player_length", self.player_length),
+            ("player_height", self.player_height),
+            ("player_acceleration", self.player_acceleration),
+            ("player_max_speed", self.player_max_speed)
+        ];
+        for &(name, value) in sizes.iter() {
+            if value.is_nan() || value <= 0.0 {
+                return invalid(format!("{} must be positive but is {}", name, value));
+            }
+        }
+        if self.player_friction.is_nan() || self.player_friction < 0.0 {
+            return invalid(format!("player_friction must not be negative but is {}", self.player_friction));
+        }
+        Ok(())
+    }
+
+    // Checks the player starts inside a branch of the tree they will play on
+    pub fn validate_start(&self, tree: &TreeData) -> Result<(), SetupError> {
+        let id = self.player_start.get_branch_id();
+        let pos = self.player_start.get_branch_position();
+        let logical = tree
+            .try_get_branch(id)
+            .map_err(|_| SetupError::Invalid(format!("player_start is on branch {} {}, which is not in the tree", id.id, id.layer)))?
+            .get_logical();
+        if !(pos.x >= 0.0 && pos.x <= logical.length && pos.y.abs() <= logical.get_half_width(pos.x)) {
+            return Err(SetupError::Invalid(format!("player_start ({}, {}) is outside of branch {} {}", pos.x, pos.y, id.id, id.layer)));
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_config_string().as_bytes())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GameSetup, SetupError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        GameSetup::from_config_str(&contents)
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let start_id = self.player_start.get_branch_id();
+        let start_pos = self.player_start.get_branch_position();
+        let mut output = String::new();
+        output += &format!("layers {}\n", self.num_layers);
+        output += &format!("vertical_fill {}\n", self.vertical_fill);
+        output += &format!("horizontal_fill {}\n", self.horizontal_fill);
+        if let Some(seed) = self.seed {
+            output += &format!("seed {}\n", seed);
+        }
+        output += &format!("player_start {} {} {} {}\n", start_id.id, start_id.layer, start_pos.x, start_pos.y);
+        output += &format!("player_size {} {}\n", self.player_length, self.player_height);
+        output += &format!("player_acceleration {}\n", self.player_acceleration);
+        output += &format!("player_max_speed {}\n", self.player_max_speed);
+        output += &format!("player_friction {}\n", self.player_friction);
+        output += &format!("enemies {}\n", self.enemy_count);
+        output += &format!("difficulty {}\n", self.difficulty.name());
+        output
+    }
+
+    pub fn from_config_str(contents: &str) -> Result<GameSetup, SetupError> {
+        let mut setup = GameSetup::default();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            setup.apply_line(line).map_err(|message| SetupError::Parse { line: line_number, message })?;
+        }
+        setup.validate()?;
+        Ok(setup)
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap_or("");
+        let values: Vec<&str> = words.collect();
+        let expect = |count: usize| if values.len() == count {
+            Ok(())
+        }
+        else {
+            Err(format!("'{}' expects {} values but found {}", key, count, values.len()))
+        };
+
+        match key {
+            "layers" => {
+                expect(1)?;
+                self.num_layers = parse_value(values[0])?;
+            },
+            "vertical_fill" => {
+                expect(1)?;
+                self.vertical_fill = parse_value(values[0])?;
+            },
+            "horizontal_fill" => {
+                expect(1)?;
+                self.horizontal_fill = parse_value(values[0])?;
+            },
+            "seed" => {
+                expect(1)?;
+                self.seed = Some(parse_value(values[0])?);
+            },
+            "player_start" => {
+                expect(4)?;
+                let id = BranchId::new(parse_value(values[0])?, parse_value(values[1])?);
+                let pos = Vector2::new(parse_value(values[2])?, parse_value(values[3])?);
+                self.player_start = Position::new(id, pos);
+            },
+            "player_size" => {
+                expect(2)?;
+                self.player_length = parse_value(values[0])?;
+                self.player_height = parse_value(values[1])?;
+            },
+            "player_acceleration" => {
+                expect(1)?;
+                self.player_acceleration = parse_value(values[0])?;
+            },
+            "player_max_speed" => {
+                expect(1)?;
+                self.player_max_speed = parse_value(values[0])?;
+            },
+            "player_friction" => {
+                expect(1)?;
+                self.player_friction = parse_value(values[0])?;
+            },
+            "enemies" => {
+                expect(1)?;
+                self.enemy_count = parse_value(values[0])?;
+            },
+            "difficulty" => {
+                expect(1)?;
+                self.difficulty = Difficulty::from_name(values[0])
+                    .ok_or_else(|| format!("Unknown difficulty '{}'", values[0]))?;
+            },
+            _ => return Err(format!("Unknown setting '{}'", key))
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T: ::std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Could not parse '{}'", value))
+}
+
+impl Default for GameSetup {
+    fn default() -> Self {
+        let body = PhysicsBody::default();
+        GameSetup {
+            num_layers: 4,
+            vertical_fill: 0.5,
+            horizontal_fill: 0.3,
+            seed: None,
+            player_start: Position::new(BranchId::new(0, 0), Vector2::new(0.25, 0.0)),
+            player_length: 0.5,
+            player_height: 0.1,
+            player_acceleration: body.acceleration,
+            player_max_speed: body.max_speed,
+            player_friction: body.friction,
+            enemy_count: 3,
+            difficulty: Difficulty::Normal
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Draws a tree and the objects on it as an SVG image, for looking at trees without a display
+use super::position::Position;
+use super::tree::{Tree, TreeData, TreeBranch, BranchRect, BoundingBox, Boundary};
+use super::footprint::get_footprint;
+use super::collision::Collidable;
+use super::theme::Theme;
+use na::{Vector2, Vector4};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Clone)]
+pub struct SvgOptions {
+    // Pixels per world unit
+    pub scale: f64,
+    // Pixels of empty space around the tree
+    pub margin: f64,
+    pub show_labels: bool,
+    pub show_connections: bool,
+    pub show_entities: bool,
+    pub theme: Theme
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            scale: 200.0,
+            margin: 20.0,
+            show_labels: false,
+            show_connections: false,
+            show_entities: true,
+            theme: Theme::default()
+        }
+    }
+}
+
+pub struct SvgExport<'a> {
+    tree: &'a TreeData,
+    options: SvgOptions,
+    entities: Vec<BranchRect>,
+    bounds: BoundingBox
+}
+
+impl<'a> SvgExport<'a> {
+    pub fn new(tree: &'a TreeData, options: SvgOptions) -> SvgExport<'a> {
+        let mut branches = tree.get_branches().values();
+        let mut bounds = match branches.next() {
+            Some(branch) => BoundingBox::from_branch(branch),
+            None => BoundingBox::new(Vector2::new(0.0, 0.0), Vector2::new(0.0, 0.0))
+        };
+        for branch in branches {
+            let branch_bounds = BoundingBox::from_branch(branch);
+            bounds.include(branch_bounds.min);
+            bounds.include(branch_bounds.max);
+        }
+
+        SvgExport {
+            tree,
+            options,
+            entities: Vec::new(),
+            bounds
+        }
+    }
+
+    // Entities that are not on the tree are left out
+    pub fn add_entity(&mut self, pos: Position, length: f64, height: f64, color: Vector4<f64>) {
+        if let Ok(mut footprint) = get_footprint(self.tree, pos, length, height, color) {
+            self.entities.append(&mut footprint);
+        }
+    }
+
+    pub fn add_collidable(&mut self, entity: &dyn Collidable, color: Vector4<f64>) {
+        self.add_entity(entity.get_position(), entity.get_length(), entity.get_height(), color);
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_svg_string().as_bytes())
+    }
+
+    pub fn to_svg_string(&self) -> String {
+        let size = (self.bounds.max - self.bounds.min) * self.options.scale
+            + Vector2::new(2.0, 2.0) * self.options.margin;
+        let mut output = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.2} {:.2}\">\n",
+            size.x.ceil(), size.y.ceil(), size.x, size.y
+        );
+
+        // Sorted so the same tree always gives the same file
+        let mut branches: Vec<&TreeBranch> = self.tree.get_branches().values().collect();
+        branches.sort_by_key(|branch| (branch.get_id().layer, branch.get_id().id));
+
+        for branch in &branches {
+            let color = self.options.theme.get_branch_color(self.tree, branch, self.tree.get_max_depth());
+            output += &format!("  <path d=\"{}\" {}/>\n", self.get_branch_path(branch), fill_style(color));
+        }
+
+        if self.options.show_connections {
+            for branch in &branches {
+                for connection in branch.get_connections() {
+                    let (t, interval) = match connection.get_boundary() {
+                        Boundary::Left(start, end) => (0.0, (start, end)),
+                        Boundary::Right(start, end) => (1.0, (start, end))
+                    };
+                    let start = self.to_svg(branch.get_visual().get_point(t, interval.0));
+                    let end = self.to_svg(branch.get_visual().get_point(t, interval.1));
+                    output += &format!(
+                        "  <line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"#ffd700\" stroke-width=\"2\"/>\n",
+                        start.x, start.y, end.x, end.y
+                    );
+                }
+            }
+        }
+
+        if self.options.show_entities {
+            for rect in &self.entities {
+                output += &format!("  <polygon points=\"{}\" {}/>\n", self.get_rect_points(rect), fill_style(rect.color));
+            }
+        }
+
+        if self.options.show_labels {
+            let font_size = (self.options.scale * 0.08).max(6.0);
+            for branch in &branches {
+                let id = branch.get_id();
+                let centre = self.to_svg(branch.get_visual().get_point(0.5, 0.0));
+                output += &format!(
+                    "  <text x=\"{:.2}\" y=\"{:.2}\" font-family=\"monospace\" font-size=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"#ffffff\">{},{}</text>\n",
+                    centre.x, centre.y, font_size, id.id, id.layer
+                );
+            }
+        }
+
+        output += "</svg>\n";
+        output
+    }
+
+    // The edges along the top and bottom of the patch are the control curve shifted by half its width
+    fn get_branch_path(&self, branch: &TreeBranch) -> String {
+        let visual = branch.get_visual();
+        let (first, second, third) = visual.get_control_points();
+        let offset = visual.patch.vert_dir * visual.patch.width / 2.0;
+        let point = |control: Vector2<f64>, side: f64| self.to_svg(visual.patch.pos + control - first + offset * side);
+
+        let (top_start, top_control, top_end) = (point(first, 1.0), point(second, 1.0), point(third, 1.0));
+        let (bottom_start, bottom_control, bottom_end) = (point(first, -1.0), point(second, -1.0), point(third, -1.0));
+        format!(
+            "M {:.2} {:.2} Q {:.2} {:.2} {:.2} {:.2} L {:.2} {:.2} Q {:.2} {:.2} {:.2} {:.2} Z",
+            top_start.x, top_start.y, top_control.x, top_control.y, top_end.x, top_end.y,
+            bottom_end.x, bottom_end.y, bottom_control.x, bottom_control.y, bottom_start.x, bottom_start.y
+        )
+    }
+
+    fn get_rect_points(&self, rect: &BranchRect) -> String {
+        let half_size = Vector2::new(rect.length / 2.0, rect.height / 2.0);
+        let corners = [
+            Vector2::new(-half_size.x, -half_size.y),
+            Vector2::new(half_size.x, -half_size.y),
+            Vector2::new(half_size.x, half_size.y),
+            Vector2::new(-half_size.x, half_size.y)
+        ];
+        let points: Vec<String> = corners
+            .iter()
+            .map(|corner| {
+                let world = self.tree.get_world_position(Position::new(rect.branch_id, rect.pos + corner));
+                let point = self.to_svg(world);
+                format!("{:.2},{:.2}", point.x, point.y)
+            })
+            .collect();
+        points.join(" ")
+    }
+
+    // SVG's y axis points down while the world's points up
+    fn to_svg(&self, world: Vector2<f64>) -> Vector2<f64> {
+        Vector2::new(
+            (world.x - self.bounds.min.x) * self.options.scale + self.options.margin,
+            (self.bounds.max.y - world.y) * self.options.scale + self.options.margin
+        )
+    }
+}
+
+fn fill_style(color: Vector4<f64>) -> String {
+    let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "fill=\"#{:02x}{:02x}{:02x}\" fill-opacity=\"{}\"",
+        channel(color.x), channel(color.y), channel(color.z), color.w
+    )
+}
@@ -0,0 +1,310 @@
+// Colours for the tree and everything on it. Theme files give one colour setting per line and
+// start from the classic theme, e.g.
+//
+//   name autumn
+//   branches gradient 0.4 0.26 0.13 1 0.9 0.5 0.1 1
+//   player 1 1 1 1
+use super::tree::{Tree, TreeBranch, BranchId};
+use na::Vector4;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+lazy_static! {
+    static ref PLAYER_COLOR: Vector4<f64> = Vector4::new(1.0, 1.0, 1.0, 1.0);
+    static ref ENEMY_COLOR: Vector4<f64> = Vector4::new(1.0, 0.2, 0.2, 1.0);
+    static ref FRUIT_COLOR: Vector4<f64> = Vector4::new(1.0, 0.6, 0.1, 1.0);
+    static ref LEAF_COLOR: Vector4<f64> = Vector4::new(0.3, 0.9, 0.3, 1.0);
+}
+
+pub const BUILT_IN_THEMES: [&str; 4] = ["classic", "bark", "okabe-ito", "high-contrast"];
+
+// What a colouring rule gets to know about each branch
+#[derive(Copy, Clone, Debug)]
+pub struct BranchInfo {
+    pub id: BranchId,
+    // Trunks start a layer, the branches they split into share it
+    pub is_trunk: bool,
+    // 0 at the root up to 1 on the outermost layer
+    pub depth: f64
+}
+
+impl BranchInfo {
+    pub fn new(tree: &dyn Tree, branch: &TreeBranch, max_depth: usize) -> BranchInfo {
+        let id = branch.get_id();
+        let is_trunk = match tree.get_parent(id) {
+            Ok(Some(parent)) => parent.layer != id.layer,
+            _ => true
+        };
+        BranchInfo {
+            id,
+            is_trunk,
+            depth: if max_depth == 0 { 0.0 } else { id.layer as f64 / max_depth as f64 }
+        }
+    }
+}
+
+// Picks the colour of a branch from what is known about it
+pub type BranchRule = Rc<dyn Fn(&BranchInfo) -> Vector4<f64>>;
+
+#[derive(Clone)]
+pub enum BranchColoring {
+    // Keeps the colours the tree was built or loaded with
+    Level,
+    Uniform(Vector4<f64>),
+    // Colour stops spread evenly from the root to the outermost layer
+    Gradient(Vec<Vector4<f64>>),
+    ByType { trunk: Vector4<f64>, branch: Vector4<f64> },
+    // Only lives in code, so it can't be saved to a theme file
+    Custom(BranchRule)
+}
+
+impl BranchColoring {
+    pub fn get_color(&self, info: &BranchInfo, level_color: Vector4<f64>) -> Vector4<f64> {
+        match *self {
+            BranchColoring::Level => level_color,
+            BranchColoring::Uniform(color) => color,
+            BranchColoring::Gradient(ref stops) => sample_gradient(stops, info.depth).unwrap_or(level_color),
+            BranchColoring::ByType { trunk, branch } => if info.is_trunk { trunk } else { branch },
+            BranchColoring::Custom(ref rule) => rule(info)
+        }
+    }
+}
+
+fn sample_gradient(stops: &[Vector4<f64>], t: f64) -> Option<Vector4<f64>> {
+    match stops.len() {
+        0 => None,
+        1 => Some(stops[0]),
+        count => {
+            let scaled = t.clamp(0.0, 1.0) * (count - 1) as f64;
+            let index = (scaled.floor() as usize).min(count - 2);
+            let fraction = scaled - index as f64;
+            Some(stops[index] * (1.0 - fraction) + stops[index + 1] * fraction)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    // The named theme colours its branches with a custom rule
+    CustomRule(String)
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ThemeError::Io(ref err) => write!(f, "Could not access theme file: {}", err),
+            ThemeError::Parse { line, ref message } => write!(f, "Theme file line {}: {}", line, message),
+            ThemeError::CustomRule(ref name) => write!(f, "Theme '{}' colours its branches with a custom rule, which can't be saved", name)
+        }
+    }
+}
+
+impl From<io::Error> for ThemeError {
+    fn from(err: io::Error) -> Self {
+        ThemeError::Io(err)
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub branches: BranchColoring,
+    pub player: Vector4<f64>,
+    pub enemy: Vector4<f64>,
+    pub fruit: Vector4<f64>,
+    pub leaf: Vector4<f64>
+}
+
+impl Theme {
+    pub fn classic() -> Theme {
+        Theme {
+            name: "classic".to_string(),
+            branches: BranchColoring::Level,
+            player: *PLAYER_COLOR,
+            enemy: *ENEMY_COLOR,
+            fruit: *FRUIT_COLOR,
+            leaf: *LEAF_COLOR
+        }
+    }
+
+    // Brown bark at the root fading to green leaves at the tips
+    pub fn bark() -> Theme {
+        Theme {
+            name: "bark".to_string(),
+            branches: BranchColoring::Gradient(vec![
+                Vector4::new(0.4, 0.26, 0.13, 1.0),
+                Vector4::new(0.45, 0.4, 0.15, 1.0),
+                Vector4::new(0.2, 0.6, 0.2, 1.0)
+            ]),
+            player: Vector4::new(1.0, 1.0, 0.8, 1.0),
+            enemy: Vector4::new(0.8, 0.1, 0.4, 1.0),
+            fruit: Vector4::new(0.9, 0.2, 0.1, 1.0),
+            leaf: Vector4::new(0.6, 0.9, 0.3, 1.0)
+        }
+    }
+
+    // The Okabe-Ito palette, which stays distinguishable under the common forms of colour blindness
+    pub fn okabe_ito() -> Theme {
+        Theme {
+            name: "okabe-ito".to_string(),
+            branches: BranchColoring::ByType {
+                trunk: Vector4::new(0.0, 0.447, 0.698, 1.0),
+                branch: Vector4::new(0.337, 0.706, 0.914, 1.0)
+            },
+            player: Vector4::new(0.941, 0.894, 0.259, 1.0),
+            enemy: Vector4::new(0.835, 0.369, 0.0, 1.0),
+            fruit: Vector4::new(0.902, 0.624, 0.0, 1.0),
+            leaf: Vector4::new(0.0, 0.62, 0.451, 1.0)
+        }
+    }
+
+    // Bright shapes on dark branches, with yellow enemies so hazards stand out the most
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high-contrast".to_string(),
+            branches: BranchColoring::Gradient(vec![
+                Vector4::new(0.15, 0.15, 0.15, 1.0),
+                Vector4::new(0.35, 0.35, 0.35, 1.0)
+            ]),
+            player: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            enemy: Vector4::new(1.0, 0.9, 0.0, 1.0),
+            fruit: Vector4::new(0.9, 0.9, 0.9, 1.0),
+            leaf: Vector4::new(0.65, 0.65, 0.65, 1.0)
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "bark" => Some(Theme::bark()),
+            "okabe-ito" => Some(Theme::okabe_ito()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None
+        }
+    }
+
+    pub fn get_branch_color(&self, tree: &dyn Tree, branch: &TreeBranch, max_depth: usize) -> Vector4<f64> {
+        let info = BranchInfo::new(tree, branch, max_depth);
+        self.branches.get_color(&info, branch.get_visual().color)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ThemeError> {
+        let contents = self.to_theme_string()?;
+        let mut file = File::create(path)?;
+        file.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Theme, ThemeError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        Theme::from_theme_str(&contents)
+    }
+
+    // Fails for custom branch rules rather than writing a theme that would load with other colours
+    pub fn to_theme_string(&self) -> Result<String, ThemeError> {
+        let mut output = format!("name {}\n", self.name);
+        output += &match self.branches {
+            BranchColoring::Level => "branches level\n".to_string(),
+            BranchColoring::Custom(_) => return Err(ThemeError::CustomRule(self.name.clone())),
+            BranchColoring::Uniform(color) => format!("branches uniform {}\n", format_color(color)),
+            BranchColoring::Gradient(ref stops) => {
+                let stops: Vec<String> = stops.iter().map(|&stop| format_color(stop)).collect();
+                format!("branches gradient {}\n", stops.join(" "))
+            },
+            BranchColoring::ByType { trunk, branch } => format!("branches type {} {}\n", format_color(trunk), format_color(branch))
+        };
+        output += &format!("player {}\n", format_color(self.player));
+        output += &format!("enemy {}\n", format_color(self.enemy));
+        output += &format!("fruit {}\n", format_color(self.fruit));
+        output += &format!("leaf {}\n", format_color(self.leaf));
+        Ok(output)
+    }
+
+    pub fn from_theme_str(contents: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::classic();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            theme.apply_line(line).map_err(|message| ThemeError::Parse { line: line_number, message })?;
+        }
+        Ok(theme)
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap_or("");
+        let values: Vec<&str> = words.collect();
+        match key {
+            "name" => self.name = values.join(" "),
+            "player" => self.player = parse_color(&values)?,
+            "enemy" => self.enemy = parse_color(&values)?,
+            "fruit" => self.fruit = parse_color(&values)?,
+            "leaf" => self.leaf = parse_color(&values)?,
+            "branches" => self.branches = parse_branch_coloring(&values)?,
+            _ => return Err(format!("Unknown setting '{}'", key))
+        }
+        Ok(())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
+
+fn format_color(color: Vector4<f64>) -> String {
+    format!("{} {} {} {}", color.x, color.y, color.z, color.w)
+}
+
+fn parse_color(values: &[&str]) -> Result<Vector4<f64>, String> {
+    if values.len() != 4 {
+        return Err(format!("Expected a colour of 4 values but found {}", values.len()));
+    }
+    let mut channels = [0.0; 4];
+    for (channel, value) in channels.iter_mut().zip(values) {
+        *channel = value.parse().map_err(|_| format!("Could not parse '{}'", value))?;
+        if *channel < 0.0 || *channel > 1.0 {
+            return Err(format!("Colour channel {} is outside of [0, 1]", value));
+        }
+    }
+    Ok(Vector4::new(channels[0], channels[1], channels[2], channels[3]))
+}
+
+fn parse_colors(values: &[&str]) -> Result<Vec<Vector4<f64>>, String> {
+    if values.is_empty() {
+        return Err("Expected at least one colour".to_string());
+    }
+    // A short last colour is reported by parse_color
+    values.chunks(4).map(parse_color).collect()
+}
+
+fn parse_branch_coloring(values: &[&str]) -> Result<BranchColoring, String> {
+    let (rule, values) = match values.split_first() {
+        Some((rule, values)) => (*rule, values),
+        None => return Err("Expected a branch colouring rule".to_string())
+    };
+    match rule {
+        "level" if values.is_empty() => Ok(BranchColoring::Level),
+        "uniform" => Ok(BranchColoring::Uniform(parse_color(values)?)),
+        "gradient" => Ok(BranchColoring::Gradient(parse_colors(values)?)),
+        "type" => {
+            let colors = parse_colors(values)?;
+            if colors.len() != 2 {
+                return Err(format!("Expected a trunk and a branch colour but found {} colours", colors.len()));
+            }
+            Ok(BranchColoring::ByType { trunk: colors[0], branch: colors[1] })
+        },
+        _ => Err(format!("Unknown branch colouring '{}'", rule))
+    }
+}
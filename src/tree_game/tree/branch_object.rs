@@ -1,6 +1,7 @@
 use tree_game::tree::Tree;
-use gg::rendering::Renderable;
+use tree_game::raster::RasterShape;
 
 pub trait BranchObject {
-    fn get_renderables(&self, tree: &Tree) -> Vec<Box<Renderable>>;
+    // What to draw, for both the renderer (see rendering) and the software rasterizer
+    fn get_raster_shapes(&self, tree: &dyn Tree) -> Vec<RasterShape>;
 }
@@ -39,9 +39,9 @@ pub enum Boundary {
 
 impl Boundary {
     pub fn get_interval(&self) -> Interval {
-        match self {
-            &Boundary::Left(start, end) => Interval::new(start, end),
-            &Boundary::Right(start, end) => Interval::new(start, end),
+        match *self {
+            Boundary::Left(start, end) => Interval::new(start, end),
+            Boundary::Right(start, end) => Interval::new(start, end),
         }
     }
 }
@@ -0,0 +1,48 @@
+use tree_game::position::Position;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EnemyBehaviour {
+    // Walks back and forth along the branch it starts on
+    Patrol,
+    // Drifts around at random, crossing onto whichever branch it reaches
+    Wander,
+    // Follows the shortest path along the tree towards the player
+    Chase
+}
+
+impl EnemyBehaviour {
+    pub const ALL: [EnemyBehaviour; 3] = [EnemyBehaviour::Patrol, EnemyBehaviour::Wander, EnemyBehaviour::Chase];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            EnemyBehaviour::Patrol => "patrol",
+            EnemyBehaviour::Wander => "wander",
+            EnemyBehaviour::Chase => "chase"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EnemyBehaviour> {
+        match name {
+            "patrol" => Some(EnemyBehaviour::Patrol),
+            "wander" => Some(EnemyBehaviour::Wander),
+            "chase" => Some(EnemyBehaviour::Chase),
+            _ => None
+        }
+    }
+}
+
+// Where an enemy starts, as stored with the tree
+#[derive(Copy, Clone, Debug)]
+pub struct EnemySpawn {
+    pub behaviour: EnemyBehaviour,
+    pub position: Position
+}
+
+impl EnemySpawn {
+    pub fn new(behaviour: EnemyBehaviour, position: Position) -> EnemySpawn {
+        EnemySpawn {
+            behaviour,
+            position
+        }
+    }
+}
@@ -0,0 +1,319 @@
+// Plain text level format, one property per line:
+//
+//   max_depth 3
+//   pickup <fruit|leaf> <id> <layer> <x> <y>
+//   enemy <patrol|wander|chase> <id> <layer> <x> <y>
+//   branch <id> <layer>
+//   logical <left_width> <right_width> <length>
+//   control <x0> <y0> <x1> <y1> <x2> <y2>
+//   vert_dir <x> <y>
+//   width <width>
+//   pos <x> <y>
+//   color <r> <g> <b> <a>
+//   connection <id> <layer> <left|right> <start> <end>
+//
+// Property lines apply to the branch most recently started, while max_depth, pickup and enemy
+// lines may appear anywhere; '#' starts a comment.
+use super::{TreeData, TreeBranch, BranchId, Connection, Boundary, PickupSpawn, PickupKind, EnemySpawn, EnemyBehaviour, ValidationError};
+use tree_game::position::Position;
+use super::tree_branch::{LogicalSpec, VisualSpec};
+use gg::geometry::{BezierPatch, BezierQuad};
+use na::{Vector2, Vector4};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    // The file parsed but describes a tree that would break during play
+    Invalid(Vec<ValidationError>)
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LevelError::Io(ref err) => write!(f, "Could not access level file: {}", err),
+            LevelError::Parse { line, ref message } => write!(f, "Level file line {}: {}", line, message),
+            LevelError::Invalid(ref errors) => {
+                write!(f, "Level is invalid:")?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl TreeData {
+    pub fn save_level<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_level_string().as_bytes())
+    }
+
+    pub fn load_level<P: AsRef<Path>>(path: P) -> Result<TreeData, LevelError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        TreeData::from_level_str(&contents)
+    }
+
+    // FNV-1a hash of the level file, for checking two trees are the same without keeping a copy
+    pub fn get_fingerprint(&self) -> u64 {
+        self.to_level_string()
+            .bytes()
+            .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    }
+
+    pub fn to_level_string(&self) -> String {
+        let mut ids: Vec<&BranchId> = self.branches.keys().collect();
+        ids.sort_by_key(|id| (id.layer, id.id));
+
+        let mut output = format!("max_depth {}\n", self.max_depth);
+        for pickup in &self.pickups {
+            let id = pickup.position.get_branch_id();
+            let pos = pickup.position.get_branch_position();
+            output += &format!("pickup {} {} {} {} {}\n", pickup.kind.name(), id.id, id.layer, pos.x, pos.y);
+        }
+        for enemy in &self.enemies {
+            let id = enemy.position.get_branch_id();
+            let pos = enemy.position.get_branch_position();
+            output += &format!("enemy {} {} {} {} {}\n", enemy.behaviour.name(), id.id, id.layer, pos.x, pos.y);
+        }
+        for id in ids {
+            let branch = &self.branches[id];
+            let logical = branch.get_logical();
+            let patch = branch.get_visual().patch;
+            let color = branch.get_visual().color;
+            let (first, second, third) = branch.get_visual().get_control_points();
+
+            output += &format!("\nbranch {} {}\n", id.id, id.layer);
+            output += &format!("logical {} {} {}\n", logical.left_width, logical.right_width, logical.length);
+            output += &format!("control {} {} {} {} {} {}\n", first.x, first.y, second.x, second.y, third.x, third.y);
+            output += &format!("vert_dir {} {}\n", patch.vert_dir.x, patch.vert_dir.y);
+            output += &format!("width {}\n", patch.width);
+            output += &format!("pos {} {}\n", patch.pos.x, patch.pos.y);
+            output += &format!("color {} {} {} {}\n", color.x, color.y, color.z, color.w);
+            for connection in branch.get_connections() {
+                let (side, start, end) = match connection.get_boundary() {
+                    Boundary::Left(start, end) => ("left", start, end),
+                    Boundary::Right(start, end) => ("right", start, end)
+                };
+                let other = connection.get_branch_id();
+                output += &format!("connection {} {} {} {} {}\n", other.id, other.layer, side, start, end);
+            }
+        }
+        output
+    }
+
+    pub fn from_level_str(contents: &str) -> Result<TreeData, LevelError> {
+        let mut branches = HashMap::new();
+        let mut max_depth = None;
+        let mut pickups = Vec::new();
+        let mut enemies = Vec::new();
+        let mut current: Option<PartialBranch> = None;
+
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or("");
+            let values: Vec<&str> = words.collect();
+            let parse_error = |message: String| LevelError::Parse { line: line_number, message };
+
+            if key == "max_depth" {
+                max_depth = Some(parse_usizes(&values, 1).map_err(&parse_error)?[0]);
+                continue;
+            }
+            if key == "pickup" {
+                if values.len() != 5 {
+                    return Err(parse_error(format!("Expected 5 values but found {}", values.len())));
+                }
+                let kind = PickupKind::from_name(values[0])
+                    .ok_or_else(|| parse_error(format!("Unknown pickup '{}'", values[0])))?;
+                let id = parse_usizes(&values[1..3], 2).map_err(&parse_error)?;
+                let pos = parse_floats(&values[3..5], 2).map_err(&parse_error)?;
+                pickups.push(PickupSpawn::new(kind, Position::new(BranchId::new(id[0], id[1]), Vector2::new(pos[0], pos[1]))));
+                continue;
+            }
+            if key == "enemy" {
+                if values.len() != 5 {
+                    return Err(parse_error(format!("Expected 5 values but found {}", values.len())));
+                }
+                let behaviour = EnemyBehaviour::from_name(values[0])
+                    .ok_or_else(|| parse_error(format!("Unknown enemy '{}'", values[0])))?;
+                let id = parse_usizes(&values[1..3], 2).map_err(&parse_error)?;
+                let pos = parse_floats(&values[3..5], 2).map_err(&parse_error)?;
+                enemies.push(EnemySpawn::new(behaviour, Position::new(BranchId::new(id[0], id[1]), Vector2::new(pos[0], pos[1]))));
+                continue;
+            }
+            if key == "branch" {
+                if let Some(partial) = current.take() {
+                    let branch = partial.finish().map_err(&parse_error)?;
+                    branches.insert(branch.get_id(), branch);
+                }
+                let id = parse_usizes(&values, 2).map_err(&parse_error)?;
+                let branch_id = BranchId::new(id[0], id[1]);
+                if branches.contains_key(&branch_id) {
+                    return Err(parse_error(format!("Branch {:?} is defined twice", branch_id)));
+                }
+                current = Some(PartialBranch::new(branch_id));
+                continue;
+            }
+
+            let partial = match current {
+                Some(ref mut partial) => partial,
+                None => return Err(parse_error(format!("'{}' appears before any branch", key)))
+            };
+            match key {
+                "logical" => {
+                    let v = parse_floats(&values, 3).map_err(&parse_error)?;
+                    partial.logical = Some(LogicalSpec::new_logical_trapezoid(v[0], v[1], v[2]));
+                },
+                "control" => {
+                    let v = parse_floats(&values, 6).map_err(&parse_error)?;
+                    partial.control = Some(BezierQuad::new(
+                        Vector2::new(v[0], v[1]),
+                        Vector2::new(v[2], v[3]),
+                        Vector2::new(v[4], v[5])
+                    ));
+                },
+                "vert_dir" => {
+                    let v = parse_floats(&values, 2).map_err(&parse_error)?;
+                    partial.vert_dir = Vector2::new(v[0], v[1]);
+                },
+                "width" => {
+                    partial.width = Some(parse_floats(&values, 1).map_err(&parse_error)?[0]);
+                },
+                "pos" => {
+                    let v = parse_floats(&values, 2).map_err(&parse_error)?;
+                    partial.pos = Some(Vector2::new(v[0], v[1]));
+                },
+                "color" => {
+                    let v = parse_floats(&values, 4).map_err(&parse_error)?;
+                    partial.color = Vector4::new(v[0], v[1], v[2], v[3]);
+                },
+                "connection" => {
+                    if values.len() != 5 {
+                        return Err(parse_error(format!("Expected 5 values but found {}", values.len())));
+                    }
+                    let id = parse_usizes(&values[0..2], 2).map_err(&parse_error)?;
+                    let interval = parse_floats(&values[3..5], 2).map_err(&parse_error)?;
+                    let boundary = match values[2] {
+                        "left" => Boundary::Left(interval[0], interval[1]),
+                        "right" => Boundary::Right(interval[0], interval[1]),
+                        side => return Err(parse_error(format!("Unknown boundary side '{}'", side)))
+                    };
+                    partial.connections.push(Connection::new(BranchId::new(id[0], id[1]), boundary));
+                },
+                _ => return Err(parse_error(format!("Unknown property '{}'", key)))
+            }
+        }
+
+        if let Some(partial) = current.take() {
+            let line_number = contents.lines().count();
+            let branch = partial.finish().map_err(|message| LevelError::Parse { line: line_number, message })?;
+            branches.insert(branch.get_id(), branch);
+        }
+
+        let max_depth = match max_depth {
+            Some(depth) => depth,
+            None => branches.keys().map(|id| id.layer).max().unwrap_or(0)
+        };
+
+        let mut tree = TreeData::new(branches, max_depth);
+        for pickup in pickups {
+            tree.add_pickup(pickup);
+        }
+        for enemy in enemies {
+            tree.add_enemy(enemy);
+        }
+
+        let errors = tree.validate();
+        if !errors.is_empty() {
+            return Err(LevelError::Invalid(errors));
+        }
+        Ok(tree)
+    }
+}
+
+struct PartialBranch {
+    id: BranchId,
+    logical: Option<LogicalSpec>,
+    control: Option<BezierQuad>,
+    vert_dir: Vector2<f64>,
+    width: Option<f64>,
+    pos: Option<Vector2<f64>>,
+    color: Vector4<f64>,
+    connections: Vec<Connection>
+}
+
+impl PartialBranch {
+    fn new(id: BranchId) -> PartialBranch {
+        PartialBranch {
+            id,
+            logical: None,
+            control: None,
+            vert_dir: Vector2::new(0.0, 1.0),
+            width: None,
+            pos: None,
+            color: Vector4::new(0.1, 0.1, 1.0, 1.0),
+            connections: Vec::new()
+        }
+    }
+
+    fn finish(self) -> Result<TreeBranch, String> {
+        let missing = |property: &str| format!("Branch {:?} is missing '{}'", self.id, property);
+        let logical = self.logical.ok_or_else(|| missing("logical"))?;
+        let control = self.control.ok_or_else(|| missing("control"))?;
+        let width = self.width.ok_or_else(|| missing("width"))?;
+        let pos = self.pos.ok_or_else(|| missing("pos"))?;
+
+        let visual = VisualSpec {
+            patch: BezierPatch {
+                control,
+                vert_dir: self.vert_dir,
+                width,
+                pos
+            },
+            color: self.color
+        };
+        Ok(TreeBranch::from_parts(self.id, visual, logical, self.connections))
+    }
+}
+
+fn parse_floats(values: &[&str], expected: usize) -> Result<Vec<f64>, String> {
+    if values.len() != expected {
+        return Err(format!("Expected {} values but found {}", expected, values.len()));
+    }
+    values.iter()
+        .map(|v| match v.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            // parse accepts "NaN" and "inf", which would break the tree's geometry
+            Ok(_) => Err(format!("'{}' is not a finite number", v)),
+            Err(_) => Err(format!("'{}' is not a number", v))
+        })
+        .collect()
+}
+
+fn parse_usizes(values: &[&str], expected: usize) -> Result<Vec<usize>, String> {
+    if values.len() != expected {
+        return Err(format!("Expected {} values but found {}", expected, values.len()));
+    }
+    values.iter()
+        .map(|v| v.parse::<usize>().map_err(|_| format!("'{}' is not a whole number", v)))
+        .collect()
+}
@@ -4,11 +4,29 @@ pub mod branch_id;
 pub mod connection;
 pub mod branch_rect;
 pub mod branch_object;
-use std::collections::HashMap;
+pub mod random_spec;
+pub mod seeded_rng;
+pub mod level_file;
+pub mod validation;
+pub mod tree_error;
+pub mod spatial_index;
+pub mod pickup_spawn;
+pub mod enemy_spawn;
+use std::collections::{HashMap, VecDeque};
+use tree_game::position::Position;
+use na::Vector2;
 
 pub use self::tree_branch::{TreeBranch, BranchSide, ConnectionIntersect};
 pub use self::branch_id::BranchId;
 pub use self::tree_builder::TreeBuilder;
+pub use self::random_spec::{RandomSpec, Distribution};
+pub use self::seeded_rng::SeededRng;
+pub use self::level_file::LevelError;
+pub use self::validation::ValidationError;
+pub use self::tree_error::TreeError;
+pub use self::spatial_index::{SpatialIndex, BoundingBox};
+pub use self::pickup_spawn::{PickupSpawn, PickupKind};
+pub use self::enemy_spawn::{EnemySpawn, EnemyBehaviour};
 pub use self::connection::{Connection, Boundary};
 pub use self::branch_object::BranchObject;
 pub use self::branch_rect::BranchRect;
@@ -19,31 +37,211 @@ pub trait Tree {
     fn get_branches(&self) -> &HashMap<BranchId, TreeBranch>;
 
     fn get_branch(&self, id: BranchId) -> &TreeBranch {
-        self.get_branches().get(&id).expect(&format!("Could not find branch with id: {:?}", id))
+        self.try_get_branch(id).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_get_branch(&self, id: BranchId) -> Result<&TreeBranch, TreeError> {
+        self.get_branches().get(&id).ok_or(TreeError::MissingBranch(id))
     }
 
     fn get_matching_connection(&self, branch: BranchId, connection: &Connection) -> &Connection {
-        let matching_branch = self.get_branch(connection.get_branch_id());
+        self.try_get_matching_connection(branch, connection).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_get_matching_connection(&self, branch: BranchId, connection: &Connection) -> Result<&Connection, TreeError> {
+        let matching_branch = self.try_get_branch(connection.get_branch_id())?;
 
         matching_branch
             .get_connections()
             .iter()
             .find(|c| { c.get_branch_id() == branch})
-            .expect(&format!("Could Not Find Matching Connection for Branch {:?} and Connection {:?}", branch, connection))
+            .ok_or_else(|| TreeError::MissingMatchingConnection { branch, connection: connection.clone() })
+    }
+
+    fn get_world_position(&self, position: Position) -> Vector2<f64> {
+        self.get_branch(position.get_branch_id()).get_world_position(position.get_branch_position())
+    }
+
+    // Where a world point lies on the tree; where branches overlap the deepest one is chosen
+    fn get_position_at(&self, world_pos: Vector2<f64>) -> Option<Position> {
+        self.get_branches()
+            .values()
+            .filter_map(|branch| {
+                branch.get_logical_position(world_pos).map(|logical_pos| Position::new(branch.get_id(), logical_pos))
+            })
+            .max_by_key(|position| {
+                let id = position.get_branch_id();
+                (id.layer, id.id)
+            })
+    }
+
+    // The branch attached to the left side, i.e. towards the root
+    fn get_parent(&self, id: BranchId) -> Result<Option<BranchId>, TreeError> {
+        Ok(self.try_get_branch(id)?
+            .get_left_connections()
+            .first()
+            .map(|c| c.get_branch_id()))
+    }
+
+    fn get_children(&self, id: BranchId) -> Result<Vec<BranchId>, TreeError> {
+        Ok(self.try_get_branch(id)?
+            .get_right_connections()
+            .iter()
+            .map(|c| c.get_branch_id())
+            .collect())
+    }
+
+    fn get_siblings(&self, id: BranchId) -> Result<Vec<BranchId>, TreeError> {
+        match self.get_parent(id)? {
+            Some(parent) => Ok(self.get_children(parent)?.into_iter().filter(|&c| c != id).collect()),
+            None => Ok(Vec::new())
+        }
+    }
+
+    // Ordered from the parent up to the root
+    fn get_ancestors(&self, id: BranchId) -> Result<Vec<BranchId>, TreeError> {
+        let mut ancestors = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.get_parent(current)? {
+            if parent == id || ancestors.contains(&parent) {
+                break;
+            }
+            ancestors.push(parent);
+            current = parent;
+        }
+        Ok(ancestors)
+    }
+
+    // Number of branches between this one and the root (unlike BranchId.layer, which counts splits)
+    fn get_depth(&self, id: BranchId) -> Result<usize, TreeError> {
+        Ok(self.get_ancestors(id)?.len())
+    }
+
+    // Fewest branches from start to end inclusive, travelling through connections in either direction;
+    // None when the two are not connected
+    fn get_path(&self, start: BranchId, end: BranchId) -> Result<Option<Vec<BranchId>>, TreeError> {
+        self.try_get_branch(start)?;
+        self.try_get_branch(end)?;
+
+        let mut previous: HashMap<BranchId, BranchId> = HashMap::new();
+        let mut queue = VecDeque::new();
+        previous.insert(start, start);
+        queue.push_back(start);
+
+        while let Some(current) = queue.pop_front() {
+            if current == end {
+                let mut path = vec![end];
+                let mut step = end;
+                while step != start {
+                    step = previous[&step];
+                    path.push(step);
+                }
+                path.reverse();
+                return Ok(Some(path));
+            }
+            for connection in self.try_get_branch(current)?.get_connections() {
+                let next = connection.get_branch_id();
+                if self.get_branches().contains_key(&next) && !previous.contains_key(&next) {
+                    previous.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Logical distance travelled along the path from the middle of start to the middle of end
+    fn get_path_distance(&self, start: BranchId, end: BranchId) -> Result<Option<f64>, TreeError> {
+        let path = match self.get_path(start, end)? {
+            Some(path) => path,
+            None => return Ok(None)
+        };
+        if path.len() == 1 {
+            return Ok(Some(0.0));
+        }
+        let last = path.len() - 1;
+        let mut distance = 0.0;
+        for (index, &id) in path.iter().enumerate() {
+            let length = self.try_get_branch(id)?.get_logical().length;
+            distance += if index == 0 || index == last { length / 2.0 } else { length };
+        }
+        Ok(Some(distance))
     }
 }
 
+#[derive(Clone)]
 pub struct TreeData {
     branches: HashMap<BranchId, TreeBranch>,
-    max_depth: usize
+    max_depth: usize,
+    index: SpatialIndex,
+    pickups: Vec<PickupSpawn>,
+    enemies: Vec<EnemySpawn>
+}
+
+impl TreeData {
+    pub fn new(branches: HashMap<BranchId, TreeBranch>, max_depth: usize) -> TreeData {
+        TreeData {
+            index: SpatialIndex::new(&branches),
+            branches,
+            max_depth,
+            pickups: Vec::new(),
+            enemies: Vec::new()
+        }
+    }
+
+    pub fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn get_pickups(&self) -> &Vec<PickupSpawn> {
+        &self.pickups
+    }
+
+    pub fn add_pickup(&mut self, pickup: PickupSpawn) {
+        self.pickups.push(pickup);
+    }
+
+    pub fn get_enemies(&self) -> &Vec<EnemySpawn> {
+        &self.enemies
+    }
+
+    pub fn add_enemy(&mut self, enemy: EnemySpawn) {
+        self.enemies.push(enemy);
+    }
+
+    pub fn get_index(&self) -> &SpatialIndex {
+        &self.index
+    }
+
+    // Branches that may overlap the rectangle, judged by their bounding boxes
+    pub fn get_branches_in_rect(&self, rect: BoundingBox) -> Vec<BranchId> {
+        self.index.query_rect(rect)
+    }
+
+    pub fn try_get_root(&self) -> Result<&TreeBranch, TreeError> {
+        self.branches.get(&BranchId::new(0, 0)).ok_or(TreeError::MissingRoot)
+    }
 }
 
 impl Tree for TreeData {
     fn get_root(&self) -> &TreeBranch {
-        self.branches.get(&BranchId::new(0, 0)).expect("No Root Branch!")
+        self.try_get_root().unwrap_or_else(|err| panic!("{}", err))
     }
 
     fn get_branches(&self) -> &HashMap<BranchId, TreeBranch> {
         &self.branches
     }
+
+    fn get_position_at(&self, world_pos: Vector2<f64>) -> Option<Position> {
+        self.index
+            .query_point(world_pos)
+            .into_iter()
+            .filter_map(|id| {
+                self.branches[&id].get_logical_position(world_pos).map(|logical_pos| Position::new(id, logical_pos))
+            })
+            .max_by_key(|position| {
+                let id = position.get_branch_id();
+                (id.layer, id.id)
+            })
+    }
 }
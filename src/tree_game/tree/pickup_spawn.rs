@@ -0,0 +1,40 @@
+use tree_game::position::Position;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PickupKind {
+    Fruit,
+    Leaf
+}
+
+impl PickupKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            PickupKind::Fruit => "fruit",
+            PickupKind::Leaf => "leaf"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PickupKind> {
+        match name {
+            "fruit" => Some(PickupKind::Fruit),
+            "leaf" => Some(PickupKind::Leaf),
+            _ => None
+        }
+    }
+}
+
+// Where a pickup starts, as stored with the tree
+#[derive(Copy, Clone, Debug)]
+pub struct PickupSpawn {
+    pub kind: PickupKind,
+    pub position: Position
+}
+
+impl PickupSpawn {
+    pub fn new(kind: PickupKind, position: Position) -> PickupSpawn {
+        PickupSpawn {
+            kind,
+            position
+        }
+    }
+}
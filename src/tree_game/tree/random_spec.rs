@@ -0,0 +1,54 @@
+use super::seeded_rng::SeededRng;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Distribution {
+    Constant(f64),
+    Uniform(f64, f64)
+}
+
+impl Distribution {
+    pub fn sample(&self, rng: &mut SeededRng) -> f64 {
+        match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform(min, max) => rng.range(min, max)
+        }
+    }
+}
+
+/// Parameters for seeded random trees; the same spec always builds the same tree
+#[derive(Copy, Clone, Debug)]
+pub struct RandomSpec {
+    pub seed: u64,
+    /// Multiplier on the default branch length for the layer
+    pub length_scale: Distribution,
+    /// Multiplier on the default rise (and so angle) of a split branch
+    pub split_angle_scale: Distribution,
+    /// Relative width of each child of a split, normalised across the split
+    pub width_weight: Distribution,
+    /// Chance that a trunk below the root splits at all
+    pub split_chance: f64,
+    /// Number of children of a split trunk, rounded to the nearest whole number (at least one)
+    pub split_count: Distribution
+}
+
+impl RandomSpec {
+    pub fn new(seed: u64) -> RandomSpec {
+        RandomSpec {
+            seed,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for RandomSpec {
+    fn default() -> Self {
+        RandomSpec {
+            seed: 0,
+            length_scale: Distribution::Uniform(0.7, 1.3),
+            split_angle_scale: Distribution::Uniform(0.5, 1.5),
+            width_weight: Distribution::Uniform(0.5, 1.5),
+            split_chance: 0.85,
+            split_count: Distribution::Constant(2.0)
+        }
+    }
+}
@@ -0,0 +1,41 @@
+/// Small xorshift generator so that a seed always reproduces the same tree,
+/// independent of any external crate's algorithm changes.
+#[derive(Copy, Clone, Debug)]
+pub struct SeededRng {
+    state: u64
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        // Scramble the seed (splitmix64) so that small seeds still give well mixed states
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z = z ^ (z >> 31);
+        SeededRng {
+            state: if z == 0 { 0x2545_F491_4F6C_DD1D } else { z }
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
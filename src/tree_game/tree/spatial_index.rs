@@ -0,0 +1,146 @@
+use super::{TreeBranch, BranchId};
+use super::tree_branch::VisualSpec;
+use na::Vector2;
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug)]
+pub struct BoundingBox {
+    pub min: Vector2<f64>,
+    pub max: Vector2<f64>
+}
+
+impl BoundingBox {
+    pub fn new(min: Vector2<f64>, max: Vector2<f64>) -> BoundingBox {
+        BoundingBox {
+            min,
+            max
+        }
+    }
+
+    pub fn from_branch(branch: &TreeBranch) -> BoundingBox {
+        BoundingBox::from_visual(&branch.get_visual())
+    }
+
+    // The patch lies within the hull of its control points swept along vert_dir
+    pub fn from_visual(visual: &VisualSpec) -> BoundingBox {
+        let (first, second, third) = visual.get_control_points();
+        let offset = visual.patch.vert_dir * visual.patch.width / 2.0;
+        let mut bounds = BoundingBox::new(visual.patch.pos, visual.patch.pos);
+        for point in &[first, second, third] {
+            let shifted = visual.patch.pos + point - first;
+            bounds.include(shifted + offset);
+            bounds.include(shifted - offset);
+        }
+        bounds
+    }
+
+    pub fn include(&mut self, point: Vector2<f64>) {
+        self.min = Vector2::new(self.min.x.min(point.x), self.min.y.min(point.y));
+        self.max = Vector2::new(self.max.x.max(point.x), self.max.y.max(point.y));
+    }
+
+    pub fn contains(&self, point: Vector2<f64>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+}
+
+// Uniform grid over the branches' bounding boxes, each cell listing the branches that may cover it
+#[derive(Clone)]
+pub struct SpatialIndex {
+    bounds: BoundingBox,
+    cell_size: Vector2<f64>,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<BranchId>>,
+    branch_bounds: HashMap<BranchId, BoundingBox>
+}
+
+impl SpatialIndex {
+    pub fn new(branches: &HashMap<BranchId, TreeBranch>) -> SpatialIndex {
+        let branch_bounds: HashMap<BranchId, BoundingBox> = branches
+            .iter()
+            .map(|(&id, branch)| (id, BoundingBox::from_branch(branch)))
+            .collect();
+
+        let mut bounds = match branch_bounds.values().next() {
+            Some(&first) => first,
+            None => BoundingBox::new(Vector2::new(0.0, 0.0), Vector2::new(0.0, 0.0))
+        };
+        for branch_box in branch_bounds.values() {
+            bounds.include(branch_box.min);
+            bounds.include(branch_box.max);
+        }
+
+        let side = ((branch_bounds.len() as f64).sqrt().ceil() as usize).max(1);
+        let size = bounds.max - bounds.min;
+        let mut index = SpatialIndex {
+            bounds,
+            cell_size: Vector2::new((size.x / side as f64).max(1e-9), (size.y / side as f64).max(1e-9)),
+            columns: side,
+            rows: side,
+            cells: vec![Vec::new(); side * side],
+            branch_bounds
+        };
+
+        let mut ids: Vec<BranchId> = index.branch_bounds.keys().cloned().collect();
+        ids.sort_by_key(|id| (id.layer, id.id));
+        for id in ids {
+            let branch_box = index.branch_bounds[&id];
+            let (min_column, min_row) = index.get_cell(branch_box.min);
+            let (max_column, max_row) = index.get_cell(branch_box.max);
+            for row in min_row..max_row + 1 {
+                for column in min_column..max_column + 1 {
+                    index.cells[row * index.columns + column].push(id);
+                }
+            }
+        }
+        index
+    }
+
+    // Branches whose bounding box contains the point
+    pub fn query_point(&self, point: Vector2<f64>) -> Vec<BranchId> {
+        if !self.bounds.contains(point) {
+            return Vec::new();
+        }
+        let (column, row) = self.get_cell(point);
+        self.cells[row * self.columns + column]
+            .iter()
+            .filter(|id| self.branch_bounds[id].contains(point))
+            .cloned()
+            .collect()
+    }
+
+    // Branches whose bounding box overlaps the rectangle
+    pub fn query_rect(&self, rect: BoundingBox) -> Vec<BranchId> {
+        if !self.bounds.intersects(&rect) {
+            return Vec::new();
+        }
+        let (min_column, min_row) = self.get_cell(rect.min);
+        let (max_column, max_row) = self.get_cell(rect.max);
+        let mut output: Vec<BranchId> = Vec::new();
+        for row in min_row..max_row + 1 {
+            for column in min_column..max_column + 1 {
+                output.extend(
+                    self.cells[row * self.columns + column]
+                        .iter()
+                        .filter(|id| self.branch_bounds[id].intersects(&rect))
+                        .cloned()
+                );
+            }
+        }
+        output.sort_by_key(|id| (id.layer, id.id));
+        output.dedup();
+        output
+    }
+
+    fn get_cell(&self, point: Vector2<f64>) -> (usize, usize) {
+        let relative = point - self.bounds.min;
+        let column = (relative.x / self.cell_size.x).floor().max(0.0) as usize;
+        let row = (relative.y / self.cell_size.y).floor().max(0.0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+}
@@ -1,7 +1,5 @@
 use na::Vector2;
-use gg::rendering::BezierLogic;
 use gg::geometry::interpolate;
-use gg::geometry::ConPoly;
 
 #[derive(Copy, Clone, Debug)]
 pub struct LogicalSpec {
@@ -15,14 +13,14 @@ impl LogicalSpec {
         LogicalSpec {
             left_width: width,
             right_width: width,
-            length: length,
+            length,
         }
     }
 
     pub fn new_logical_trapezoid(left_width: f64, right_width: f64, left_length: f64) -> LogicalSpec {
         LogicalSpec {
-            left_width: left_width,
-            right_width: right_width,
+            left_width,
+            right_width,
             length: left_length,
         }
     }
@@ -35,29 +33,8 @@ impl LogicalSpec {
         Vector2::new(point.x + shift, new_vertical)
     }
 
-    fn get_logical_con_poly(&self) -> ConPoly {
-        let bottom_left = Vector2::new(0.0, - self.left_width / 2.0);
-        let bottom_right = Vector2::new(self.length, - self.right_width / 2.0);
-        let top_right = Vector2::new(self.length, self.right_width / 2.0);
-        let top_left = Vector2::new(0.0, self.left_width / 2.0);
-
-        let corners = vec![
-            bottom_left,
-            bottom_right,
-            top_right,
-            top_left,
-        ];
-
-        ConPoly::new(corners)
-    }
-}
-
-impl From<LogicalSpec> for BezierLogic {
-    fn from (spec: LogicalSpec) -> Self {
-        BezierLogic {
-            length: spec.length,
-            width_left: spec.left_width,
-            width_right: spec.right_width
-        }
+    // Distance from the centre line to either wall at the given distance along the branch
+    pub fn get_half_width(&self, x: f64) -> f64 {
+        interpolate(self.left_width, self.right_width, x / self.length) / 2.0
     }
 }
@@ -1,12 +1,13 @@
 use na::Vector2;
 use gg::geometry::{Interval, Line, line_line_intersect_2d, DualSoln};
 //use gg::debug::*;
-use super::{BranchId, Connection, Boundary};
+use super::{BranchId, Connection, Boundary, TreeError};
 pub mod logical;
 pub mod visual;
 pub use self::logical::*;
 pub use self::visual::*;
 
+#[derive(Clone)]
 pub struct TreeBranch {
     id: BranchId,
     visual: VisualSpec,
@@ -21,22 +22,40 @@ impl TreeBranch {
         branch_type: BranchType,
         vertical_fill: f64,
         horizontal_fill: f64
+    ) -> TreeBranch {
+        TreeBranch::new_with_shape(id, pos, branch_type, BranchShape::new(id.layer, branch_type, vertical_fill, horizontal_fill))
+    }
+
+    pub fn new_with_shape(
+        id: BranchId,
+        pos: Vector2<f64>,
+        branch_type: BranchType,
+        shape: BranchShape
     ) -> TreeBranch {
         let logical_spec = match branch_type {
             BranchType::Trunk => LogicalSpec::new_logical_rect(1.0, 0.5),
-            BranchType::BranchTop | BranchType::BranchBottom => LogicalSpec::new_logical_trapezoid(0.5, 1.0, 1.0)
+            BranchType::Branch { count, .. } => LogicalSpec::new_logical_trapezoid(1.0 / count as f64, 1.0, 1.0)
         };
 
-        let visual_spec = VisualSpec::new(id.layer, pos, branch_type, vertical_fill, horizontal_fill);
+        let visual_spec = VisualSpec::from_shape(pos, shape);
 
         TreeBranch {
-            id: id,
+            id,
             logical: logical_spec,
             visual: visual_spec,
             connections: Vec::new()
         }
     }
 
+    pub fn from_parts(id: BranchId, visual: VisualSpec, logical: LogicalSpec, connections: Vec<Connection>) -> TreeBranch {
+        TreeBranch {
+            id,
+            visual,
+            logical,
+            connections
+        }
+    }
+
     pub fn add_connection(&mut self, f_bound: Boundary, second: &mut TreeBranch, s_bound: Boundary) {
         let f_connect = Connection::new(second.id, f_bound);
         let s_connect = Connection::new(self.id, s_bound);
@@ -49,7 +68,7 @@ impl TreeBranch {
     }
 
     pub fn get_logical(&self) -> LogicalSpec {
-        self.logical.clone()
+        self.logical
     }
 
     pub fn get_id(&self) -> BranchId {
@@ -61,24 +80,18 @@ impl TreeBranch {
     }
 
     pub fn get_left_connections(&self) -> Vec<&Connection> {
-        self.get_connections().iter().filter_map(|c| {
-            match c.get_boundary() {
-                Boundary::Left(_, _) => Some(c),
-                _ => None
-            }
-        }).collect()
+        self.get_connections().iter().filter(|c| matches!(c.get_boundary(), Boundary::Left(_, _))).collect()
     }
 
     pub fn get_right_connections(&self) -> Vec<&Connection> {
-        self.get_connections().iter().filter_map(|c| {
-            match c.get_boundary() {
-                Boundary::Right(_, _) => Some(c),
-                _ => None
-            }
-        }).collect()
+        self.get_connections().iter().filter(|c| matches!(c.get_boundary(), Boundary::Right(_, _))).collect()
     }
 
     pub fn get_connection(&self, pos: f64, side: BranchSide) -> Option<&Connection> {
+        self.try_get_connection(pos, side).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_connection(&self, pos: f64, side: BranchSide) -> Result<Option<&Connection>, TreeError> {
         let possible_connections = match side {
             BranchSide::Left => self.get_left_connections(),
             BranchSide::Right => self.get_right_connections()
@@ -90,9 +103,9 @@ impl TreeBranch {
             .collect();
 
         match valid_connections.len() {
-            0 => None,
-            1 => Some(valid_connections.first().unwrap()),
-            _ => panic!("Overlapping Connections on Branch with Id: {:?}", self.id)
+            0 => Ok(None),
+            1 => Ok(Some(valid_connections.first().unwrap())),
+            _ => Err(TreeError::OverlappingConnections(self.id))
         }
     }
 
@@ -124,6 +137,22 @@ impl TreeBranch {
         new_logical_position
     }
 
+    pub fn get_world_position(&self, logical_pos: Vector2<f64>) -> Vector2<f64> {
+        let logical = self.get_logical();
+        let t = logical_pos.x / logical.length;
+        let v = logical_pos.y / logical.get_half_width(logical_pos.x);
+        self.visual.get_point(t, v)
+    }
+
+    // Inverse of get_world_position, None when the point is not on this branch
+    pub fn get_logical_position(&self, world_pos: Vector2<f64>) -> Option<Vector2<f64>> {
+        self.visual.get_patch_coords(world_pos).map(|(t, v)| {
+            let logical = self.get_logical();
+            let x = t * logical.length;
+            Vector2::new(x, v * logical.get_half_width(x))
+        })
+    }
+
     fn get_single_boundary_intersect(&self, line: Line, connection: &Connection) -> Option<ConnectionIntersect> {
         let boundary_line = self.get_logical_boundary_line(connection.get_boundary());
 
@@ -143,15 +172,10 @@ impl TreeBranch {
             }
         };
 
-        match intersect_time {
-            Some(time) => Some(
-                ConnectionIntersect{
-                    connection: connection.clone(),
-                    overlap: (line.get_point(1.0) - line.get_point(time)).x.abs()
-                }
-            ),
-            None => None
-        }
+        intersect_time.map(|time| ConnectionIntersect {
+            connection: connection.clone(),
+            overlap: (line.get_point(1.0) - line.get_point(time)).x.abs()
+        })
     }
 
     pub fn get_boundary_intersect(&self, line: Line) -> Option<ConnectionIntersect> {
@@ -164,6 +188,7 @@ impl TreeBranch {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BranchSide {
     Left,
     Right
@@ -172,8 +197,8 @@ pub enum BranchSide {
 #[derive(Copy, Clone, Debug)]
 pub enum BranchType {
     Trunk,
-    BranchTop,
-    BranchBottom
+    // Child number `index`, counting down from the top, of a trunk split into `count` branches
+    Branch { index: usize, count: usize }
 }
 
 pub struct ConnectionIntersect {
@@ -1,8 +1,9 @@
 use na::{Vector2, Vector4};
 use gg::geometry::{BezierPatch, BezierQuad};
-use gg::rendering::BezierRect;
 use super::BranchType;
 
+const EPSILON: f64 = 1e-9;
+
 #[derive(Clone)]
 pub struct VisualSpec {
     pub patch: BezierPatch,
@@ -17,54 +18,104 @@ impl VisualSpec {
         vertical_fill: f64,
         horizontal_fill: f64
     ) -> VisualSpec {
-        let trunk_width = (1.0 - (1.0 - 2.0 * vertical_fill).powi((depth as i32) + 1)) / 2f64.powi((depth as i32) + 1);
-        let trunk_width_next =  (1.0 - vertical_fill.powi((depth as i32) + 2)) / 2f64.powi((depth as i32) + 2);
-        let trunk_length = horizontal_fill * (1.0 - horizontal_fill).powi(depth as i32) / 2.0;
-        let vert_dir = Vector2::<f64>::new(0.0, 1.0);
-        let width = match branch_type {
-            BranchType::Trunk => trunk_width * 2.0,
-            BranchType::BranchTop | BranchType::BranchBottom => trunk_width
-        };
-        let control = match branch_type {
-            BranchType::Trunk => BezierQuad::new(
-                Vector2::new(0.0, 0.0),
-                Vector2::new(trunk_length, 0.0),
-                Vector2::new(trunk_length * 2.0, 0.0)
-            ),
-            BranchType::BranchTop => BezierQuad::new(
-                Vector2::new(0.0, 0.0),
-                Vector2::new(trunk_length, trunk_width / 4.0),
-                Vector2::new(trunk_length * 2.0, trunk_width / 2.0)
-            ),
-            BranchType::BranchBottom => BezierQuad::new(
-                Vector2::new(0.0, 0.0),
-                Vector2::new(trunk_length, -trunk_width / 4.0),
-                Vector2::new(trunk_length * 2.0, -trunk_width / 2.0)
-            )
+        VisualSpec::from_shape(pos, BranchShape::new(depth, branch_type, vertical_fill, horizontal_fill))
+    }
+
+    // t runs along the branch from 0 to 1, v across it from -1 (bottom) to 1 (top)
+    pub fn get_point(&self, t: f64, v: f64) -> Vector2<f64> {
+        self.patch.pos
+            + self.patch.control.eval(t)
+            - self.patch.control.eval(0.0)
+            + self.patch.vert_dir * v * self.patch.width / 2.0
+    }
+
+    // The t, v coordinates of world_point when it lies on this patch, i.e. the inverse of get_point
+    pub fn get_patch_coords(&self, world_point: Vector2<f64>) -> Option<(f64, f64)> {
+        let (first, second, third) = self.get_control_points();
+        let vert_dir = self.patch.vert_dir;
+        let normal = Vector2::new(-vert_dir.y, vert_dir.x);
+        let target = world_point - self.patch.pos + first;
+
+        // Solve normal . (control(t) - target) = 0, the remainder then lies along vert_dir
+        let a = normal.dot(&(first - second * 2.0 + third));
+        let b = normal.dot(&(second - first)) * 2.0;
+        let c = normal.dot(&(first - target));
+        let roots = if a.abs() < EPSILON {
+            if b.abs() < EPSILON { vec![] } else { vec![-c / b] }
+        }
+        else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                vec![]
+            }
+            else {
+                vec![(-b + discriminant.sqrt()) / (2.0 * a), (-b - discriminant.sqrt()) / (2.0 * a)]
+            }
         };
 
+        roots.into_iter()
+            .filter(|&t| (-EPSILON..=1.0 + EPSILON).contains(&t))
+            .map(|t| {
+                let t = t.clamp(0.0, 1.0);
+                let along = (target - self.patch.control.eval(t)).dot(&vert_dir) / vert_dir.dot(&vert_dir);
+                (t, along * 2.0 / self.patch.width)
+            })
+            .find(|&(_, v)| v.abs() <= 1.0 + EPSILON)
+    }
+
+    // Recovers the three control points of the quadratic bezier from its evaluations
+    pub fn get_control_points(&self) -> (Vector2<f64>, Vector2<f64>, Vector2<f64>) {
+        let control = &self.patch.control;
+        let first = control.eval(0.0);
+        let third = control.eval(1.0);
+        let second = control.eval(0.5) * 2.0 - (first + third) / 2.0;
+        (first, second, third)
+    }
+
+    pub fn from_shape(pos: Vector2<f64>, shape: BranchShape) -> VisualSpec {
+        let control = BezierQuad::new(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(shape.length / 2.0, shape.rise / 2.0),
+            Vector2::new(shape.length, shape.rise)
+        );
+
         let patch = BezierPatch {
-            control: control,
-            vert_dir: vert_dir,
-            width: width,
-            pos: pos
+            control,
+            vert_dir: Vector2::<f64>::new(0.0, 1.0),
+            width: shape.width,
+            pos
         };
 
         VisualSpec {
-            patch: patch,
+            patch,
             color: Vector4::new(0.1, 0.1, 1.0, 1.0),
         }
     }
 }
 
-impl From<VisualSpec> for BezierRect {
-    fn from (spec: VisualSpec) -> Self {
-        BezierRect {
-            control: spec.patch.control.into(),
-            vert_dir: spec.patch.vert_dir,
-            width: spec.patch.width,
-            pos: spec.patch.pos,
-            color: spec.color
+/// Visual dimensions of a branch: it runs `length` to the right while rising by `rise`
+#[derive(Copy, Clone, Debug)]
+pub struct BranchShape {
+    pub length: f64,
+    pub width: f64,
+    pub rise: f64
+}
+
+impl BranchShape {
+    pub fn new(depth: usize, branch_type: BranchType, vertical_fill: f64, horizontal_fill: f64) -> BranchShape {
+        let trunk_width = (1.0 - (1.0 - 2.0 * vertical_fill).powi((depth as i32) + 1)) / 2f64.powi((depth as i32) + 1);
+        let trunk_length = horizontal_fill * (1.0 - horizontal_fill).powi(depth as i32) / 2.0;
+        match branch_type {
+            BranchType::Trunk => BranchShape {
+                length: trunk_length * 2.0,
+                width: trunk_width * 2.0,
+                rise: 0.0
+            },
+            BranchType::Branch { index, count } => BranchShape {
+                length: trunk_length * 2.0,
+                width: trunk_width * 2.0 / count as f64,
+                rise: trunk_width * (1.0 - (2 * index + 1) as f64 / count as f64)
+            }
         }
     }
 }
@@ -1,53 +1,161 @@
-use super::tree_branch::{BranchType, TreeBranch};
+use super::tree_branch::{BranchType, BranchShape, TreeBranch, VisualSpec, LogicalSpec};
+use super::random_spec::{RandomSpec, Distribution};
+use super::seeded_rng::SeededRng;
 use super::TreeData;
-use super::{BranchId, Boundary};
+use super::{BranchId, Boundary, PickupSpawn, PickupKind, EnemySpawn, EnemyBehaviour};
+use tree_game::position::Position;
 use na::Vector2;
 use std::collections::HashMap;
 
+// Where one branch of a split sits against the end of its trunk
+struct SplitPlacement {
+    branch_type: BranchType,
+    left_pos: Vector2<f64>,
+    trunk_width: f64,
+    // Fraction of the trunk's end this branch covers
+    share: f64,
+    rise: f64,
+    trunk_boundary: Boundary
+}
+
 pub struct TreeBuilder {
     pub num_layers: usize,
     current_indices: Vec<usize>,
     vertical_fill: f64,
     horizontal_fill: f64,
+    split_counts: Box<dyn Fn(BranchId) -> usize>,
+    random: Option<(RandomSpec, SeededRng)>,
+    pickup_chance: f64,
+    pickup_seed: u64,
+    enemy_count: usize,
+    enemy_seed: u64,
     branches: HashMap<BranchId, TreeBranch>
 }
 
 impl TreeBuilder {
     pub fn new(num_layers: usize) -> TreeBuilder {
         TreeBuilder {
-            num_layers: num_layers,
+            num_layers,
             current_indices: vec![0; num_layers],
             vertical_fill: 0.5,
             horizontal_fill: 0.3,
+            split_counts: Box::new(|_| 2),
+            random: None,
+            pickup_chance: 0.0,
+            pickup_seed: 0,
+            enemy_count: 0,
+            enemy_seed: 0,
             branches: HashMap::new()            
         }
     }
+
+    pub fn new_random(num_layers: usize, spec: RandomSpec) -> TreeBuilder {
+        TreeBuilder {
+            random: Some((spec, SeededRng::new(spec.seed))),
+            ..TreeBuilder::new(num_layers)
+        }
+    }
+
+    pub fn with_split_count(self, count: usize) -> TreeBuilder {
+        self.with_split_counts(move |_| count)
+    }
+
+    // Chooses how many branches each trunk splits into (ignored for random trees)
+    pub fn with_split_counts<F: Fn(BranchId) -> usize + 'static>(mut self, split_counts: F) -> TreeBuilder {
+        self.split_counts = Box::new(split_counts);
+        self
+    }
     
+    // vertical_fill sets the height of the root trunk, which each split then shares out between its
+    // branches; horizontal_fill is how much of its parent's length each layer takes up
+    pub fn with_fills(mut self, vertical_fill: f64, horizontal_fill: f64) -> TreeBuilder {
+        self.vertical_fill = vertical_fill;
+        self.horizontal_fill = horizontal_fill;
+        self
+    }
+
+    // Each branch gets a pickup with chance_per_layer times its layer number (counting from one)
+    pub fn with_pickups(mut self, chance_per_layer: f64, seed: u64) -> TreeBuilder {
+        self.pickup_chance = chance_per_layer;
+        self.pickup_seed = seed;
+        self
+    }
+
+    // Places up to count enemies on different branches away from the root, taking turns at each behaviour
+    pub fn with_enemies(mut self, count: usize, seed: u64) -> TreeBuilder {
+        self.enemy_count = count;
+        self.enemy_seed = seed;
+        self
+    }
+
     pub fn build_tree(mut self) -> TreeData {
-        self.build_single_branch_recursive(0, Vector2::new(-1.0, 0.0), None);
-        TreeData {
-            branches: self.branches,
-            max_depth: self.num_layers - 1
+        let root_width = BranchShape::new(0, BranchType::Trunk, self.vertical_fill, self.horizontal_fill).width;
+        self.build_single_branch_recursive(0, Vector2::new(-1.0, 0.0), root_width, None);
+        let pickups = self.place_pickups();
+        let enemies = self.place_enemies();
+        let mut tree = TreeData::new(self.branches, self.num_layers - 1);
+        for pickup in pickups {
+            tree.add_pickup(pickup);
+        }
+        for enemy in enemies {
+            tree.add_enemy(enemy);
         }
+        tree
+    }
+
+    fn place_pickups(&self) -> Vec<PickupSpawn> {
+        let mut rng = SeededRng::new(self.pickup_seed);
+        let mut ids: Vec<&BranchId> = self.branches.keys().collect();
+        ids.sort_by_key(|id| (id.layer, id.id));
+
+        let mut pickups = Vec::new();
+        for &id in ids {
+            if !rng.chance(self.pickup_chance * (id.layer + 1) as f64) {
+                continue;
+            }
+            let length = self.branches[&id].get_logical().length;
+            let kind = if rng.chance(0.3) { PickupKind::Fruit } else { PickupKind::Leaf };
+            let position = Position::new(id, Vector2::new(rng.range(0.2, 0.8) * length, 0.0));
+            pickups.push(PickupSpawn::new(kind, position));
+        }
+        pickups
+    }
+
+    fn place_enemies(&self) -> Vec<EnemySpawn> {
+        let mut rng = SeededRng::new(self.enemy_seed);
+        let mut ids: Vec<BranchId> = self.branches.keys().cloned().filter(|id| id.layer > 0).collect();
+        ids.sort_by_key(|id| (id.layer, id.id));
+
+        let mut enemies = Vec::new();
+        for index in 0..self.enemy_count.min(ids.len()) {
+            let id = ids.remove((rng.next_u64() % ids.len() as u64) as usize);
+            let length = self.branches[&id].get_logical().length;
+            let behaviour = EnemyBehaviour::ALL[index % EnemyBehaviour::ALL.len()];
+            let position = Position::new(id, Vector2::new(rng.range(0.3, 0.7) * length, 0.0));
+            enemies.push(EnemySpawn::new(behaviour, position));
+        }
+        enemies
     }
 
     ////////////////////////////////////////////////////
-    //              //          Branch Top
+    //              //          Branch 0
     ////////////////     ///////////////////////////////
-    // Trunk           /////////////////////////////////
+    // Trunk           /////////    ...    /////////////
     ////////////////     /////////////////////////////// 
-    //              //          Branch Bottom
+    //              //          Branch N - 1
     ////////////////////////////////////////////////////
-    pub fn build_single_branch_recursive(&mut self, depth: usize, left_center_pos: Vector2<f64>, parent_branch: Option<&mut TreeBranch>) {
+    pub fn build_single_branch_recursive(&mut self, depth: usize, left_center_pos: Vector2<f64>, trunk_width: f64, parent_branch: Option<&mut TreeBranch>) {
 
         // Build the trunk
         let trunk_index = self.generate_new_index(depth);
-        let mut trunk_branch = TreeBranch::new(
+        let mut trunk_shape = BranchShape::new(depth, BranchType::Trunk, self.vertical_fill, self.horizontal_fill);
+        trunk_shape.width = trunk_width;
+        trunk_shape.length *= self.sample(|spec| spec.length_scale, 1.0);
+        let mut trunk_branch = TreeBranch::new_with_shape(
             trunk_index,
             left_center_pos,
             BranchType::Trunk,
-            self.vertical_fill,
-            self.horizontal_fill
+            trunk_shape
         );
         if let Some(parent) = parent_branch {
             let trunk_boundary = Boundary::Left(-1.0, 1.0);
@@ -55,53 +163,79 @@ impl TreeBuilder {
             trunk_branch.add_connection(trunk_boundary, parent, parent_boundary); 
         }
 
-        let trunk_width =  (1.0 - (1.0 - 2.0 * self.vertical_fill).powi((depth as i32) + 1)) / 2f64.powi((depth as i32) + 1);
-        let trunk_length = self.horizontal_fill * (1.0 - self.horizontal_fill).powi(depth as i32) / 2.0;
-
-        // Build Branch Top
-        let left_top_pos = left_center_pos + Vector2::new(trunk_length * 2.0, trunk_width / 2.0);
-        let left_index = self.generate_new_index(depth);
-        let mut branch_top = TreeBranch::new (
-            left_index,
-            left_top_pos,
-            BranchType::BranchTop,
-            self.vertical_fill,
-            self.horizontal_fill
-        );
-        let left_top_end_pos = left_top_pos
-            + branch_top.get_visual().patch.control.eval(1.0)
-            - branch_top.get_visual().patch.control.eval(0.0);
-        let branch_top_boundary = Boundary::Left(-1.0, 1.0);
-        let trunk_top_boundary = Boundary::Right(0.0, 1.0);
-        branch_top.add_connection(branch_top_boundary, &mut trunk_branch, trunk_top_boundary);        
-        if depth != self.num_layers - 1 {
-            self.build_single_branch_recursive(depth + 1, left_top_end_pos, Some(&mut branch_top));
+        let count = self.sample_split_count(trunk_index);
+        let weights: Vec<f64> = (0..count).map(|_| self.sample(|spec| spec.width_weight, 1.0)).collect();
+        let total_weight: f64 = weights.iter().sum();
+        let trunk_end_pos = left_center_pos + Vector2::new(trunk_shape.length, 0.0);
+
+        // Build the branches from the top of the trunk's right boundary down
+        let mut boundary_top = 1.0;
+        for (index, weight) in weights.into_iter().enumerate() {
+            let boundary_bottom = if index == count - 1 { -1.0 } else { boundary_top - 2.0 * weight / total_weight };
+            let share = (boundary_top - boundary_bottom) / 2.0;
+            let center_offset = trunk_width * (boundary_top + boundary_bottom) / 4.0;
+            let placement = SplitPlacement {
+                branch_type: BranchType::Branch { index, count },
+                left_pos: trunk_end_pos + Vector2::new(0.0, center_offset),
+                trunk_width,
+                share,
+                rise: center_offset,
+                trunk_boundary: Boundary::Right(boundary_bottom, boundary_top)
+            };
+            self.build_split_branch(depth, &mut trunk_branch, placement);
+            boundary_top = boundary_bottom;
         }
 
-        // Build Branch Bottom 
-        let left_bottom_pos = left_center_pos + Vector2::new(trunk_length * 2.0, -trunk_width / 2.0);
-        let right_index = self.generate_new_index(depth);
-        let mut branch_bottom = TreeBranch::new(
-            right_index,
-            left_bottom_pos,
-            BranchType::BranchBottom,
-            self.vertical_fill,
-            self.horizontal_fill
+        // Add Trunk to Tree
+        self.branches.insert(trunk_index, trunk_branch);
+    }
+
+    fn build_split_branch(&mut self, depth: usize, trunk_branch: &mut TreeBranch, placement: SplitPlacement) {
+        let SplitPlacement { branch_type, left_pos, trunk_width, share, rise, trunk_boundary } = placement;
+        let index = self.generate_new_index(depth);
+        let mut shape = BranchShape::new(depth, branch_type, self.vertical_fill, self.horizontal_fill);
+        shape.width = trunk_width * share;
+        shape.length *= self.sample(|spec| spec.length_scale, 1.0);
+        shape.rise = rise * self.sample(|spec| spec.split_angle_scale, 1.0);
+        // The branch takes the same share of the trunk's logical width as of its visual width
+        let mut branch = TreeBranch::from_parts(
+            index,
+            VisualSpec::from_shape(left_pos, shape),
+            LogicalSpec::new_logical_trapezoid(share, 1.0, 1.0),
+            Vec::new()
         );
-        let left_bottom_end_pos = left_bottom_pos
-            + branch_bottom.get_visual().patch.control.eval(1.0)
-            - branch_bottom.get_visual().patch.control.eval(0.0);
-        let branch_bottom_boundary = Boundary::Left(-1.0, 1.0);
-        let trunk_bottom_boundary = Boundary::Right(-1.0, 0.0);
-        branch_bottom.add_connection(branch_bottom_boundary, &mut trunk_branch, trunk_bottom_boundary);
+        let left_end_pos = left_pos
+            + branch.get_visual().patch.control.eval(1.0)
+            - branch.get_visual().patch.control.eval(0.0);
+        let branch_boundary = Boundary::Left(-1.0, 1.0);
+        branch.add_connection(branch_boundary, trunk_branch, trunk_boundary);
         if depth != self.num_layers - 1 {
-            self.build_single_branch_recursive(depth + 1, left_bottom_end_pos, Some(&mut branch_bottom));
+            // The next trunk carries on at the width the branch ends with, so the two meet without a seam
+            let child_width = branch.get_visual().patch.width;
+            self.build_single_branch_recursive(depth + 1, left_end_pos, child_width, Some(&mut branch));
         }
+        self.branches.insert(index, branch);
+    }
 
-        // Add Branches to Tree
-        self.branches.insert(trunk_index, trunk_branch);
-        self.branches.insert(left_index, branch_top);
-        self.branches.insert(right_index, branch_bottom);
+    fn sample<F: Fn(&RandomSpec) -> Distribution>(&mut self, distribution: F, default: f64) -> f64 {
+        match self.random {
+            Some((ref spec, ref mut rng)) => distribution(spec).sample(rng),
+            None => default
+        }
+    }
+
+    fn sample_split_count(&mut self, trunk_index: BranchId) -> usize {
+        match self.random {
+            Some((ref spec, ref mut rng)) => {
+                if trunk_index.layer != 0 && !rng.chance(spec.split_chance) {
+                    0
+                }
+                else {
+                    spec.split_count.sample(rng).round().max(1.0) as usize
+                }
+            },
+            None => (self.split_counts)(trunk_index)
+        }
     }
 
     pub fn generate_new_index(&mut self, depth: usize) -> BranchId {
@@ -0,0 +1,22 @@
+use super::{BranchId, Connection};
+use std::fmt;
+
+#[derive(Clone, Debug)]
+pub enum TreeError {
+    MissingRoot,
+    MissingBranch(BranchId),
+    MissingMatchingConnection { branch: BranchId, connection: Connection },
+    OverlappingConnections(BranchId)
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TreeError::MissingRoot => write!(f, "No Root Branch!"),
+            TreeError::MissingBranch(id) => write!(f, "Could not find branch with id: {:?}", id),
+            TreeError::MissingMatchingConnection { branch, ref connection } =>
+                write!(f, "Could Not Find Matching Connection for Branch {:?} and Connection {:?}", branch, connection),
+            TreeError::OverlappingConnections(id) => write!(f, "Overlapping Connections on Branch with Id: {:?}", id)
+        }
+    }
+}
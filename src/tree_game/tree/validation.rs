@@ -0,0 +1,142 @@
+use super::{TreeData, Tree, BranchId, BranchSide, Boundary, Connection};
+use std::fmt;
+
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Debug)]
+pub enum ValidationError {
+    MissingRoot,
+    DanglingConnection { branch: BranchId, target: BranchId },
+    OneSidedConnection { branch: BranchId, target: BranchId },
+    OverlappingBoundaries { branch: BranchId, first: Boundary, second: Boundary },
+    BoundaryGap { branch: BranchId, side: BranchSide, start: f64, end: f64 },
+    BoundaryOutOfRange { branch: BranchId, boundary: Boundary },
+    DanglingPickup { branch: BranchId },
+    DanglingEnemy { branch: BranchId }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::MissingRoot =>
+                write!(f, "Tree has no root branch {:?}", BranchId::new(0, 0)),
+            ValidationError::DanglingConnection { branch, target } =>
+                write!(f, "Branch {:?} connects to missing branch {:?}", branch, target),
+            ValidationError::OneSidedConnection { branch, target } =>
+                write!(f, "Branch {:?} connects to {:?} but not the other way around", branch, target),
+            ValidationError::OverlappingBoundaries { branch, first, second } =>
+                write!(f, "Branch {:?} has overlapping boundaries {:?} and {:?}", branch, first, second),
+            ValidationError::BoundaryGap { branch, side, start, end } =>
+                write!(f, "Branch {:?} has no connection on its {:?} side between {} and {}", branch, side, start, end),
+            ValidationError::BoundaryOutOfRange { branch, boundary } =>
+                write!(f, "Branch {:?} has boundary {:?} outside of [-1, 1]", branch, boundary),
+            ValidationError::DanglingPickup { branch } =>
+                write!(f, "A pickup is placed on missing branch {:?}", branch),
+            ValidationError::DanglingEnemy { branch } =>
+                write!(f, "An enemy is placed on missing branch {:?}", branch)
+        }
+    }
+}
+
+impl TreeData {
+    // Checks the structure of the tree, so that bad trees are caught before they panic during play
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        if !self.branches.contains_key(&BranchId::new(0, 0)) {
+            errors.push(ValidationError::MissingRoot);
+        }
+
+        let mut ids: Vec<&BranchId> = self.branches.keys().collect();
+        ids.sort_by_key(|id| (id.layer, id.id));
+
+        for &id in ids {
+            let branch = self.get_branch(id);
+
+            for connection in branch.get_connections() {
+                let (start, end) = interval_ends(connection);
+                if !start.is_finite() || !end.is_finite() || start < -1.0 - TOLERANCE || end > 1.0 + TOLERANCE || start >= end {
+                    errors.push(ValidationError::BoundaryOutOfRange { branch: id, boundary: connection.get_boundary() });
+                }
+
+                let target = connection.get_branch_id();
+                match self.branches.get(&target) {
+                    None => errors.push(ValidationError::DanglingConnection { branch: id, target }),
+                    Some(target_branch) => {
+                        if !target_branch.get_connections().iter().any(|c| c.get_branch_id() == id) {
+                            errors.push(ValidationError::OneSidedConnection { branch: id, target });
+                        }
+                    }
+                }
+            }
+
+            errors.append(&mut validate_side(id, branch.get_left_connections(), BranchSide::Left));
+            errors.append(&mut validate_side(id, branch.get_right_connections(), BranchSide::Right));
+        }
+
+        for pickup in &self.pickups {
+            let branch = pickup.position.get_branch_id();
+            if !self.branches.contains_key(&branch) {
+                errors.push(ValidationError::DanglingPickup { branch });
+            }
+        }
+
+        for enemy in &self.enemies {
+            let branch = enemy.position.get_branch_id();
+            if !self.branches.contains_key(&branch) {
+                errors.push(ValidationError::DanglingEnemy { branch });
+            }
+        }
+
+        errors
+    }
+}
+
+// A side with no connections is a dead end, otherwise its connections must tile [-1, 1]
+fn validate_side(id: BranchId, connections: Vec<&Connection>, side: BranchSide) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    if connections.is_empty() {
+        return errors;
+    }
+
+    // Non-finite ends are already reported as out of range and cannot be ordered, so they leave a gap
+    let mut connections: Vec<&Connection> = connections
+        .into_iter()
+        .filter(|c| { let (start, end) = interval_ends(c); start.is_finite() && end.is_finite() })
+        .collect();
+    connections.sort_by(|a, b| interval_ends(a).0.total_cmp(&interval_ends(b).0));
+
+    for (index, first) in connections.iter().enumerate() {
+        for second in connections.iter().skip(index + 1) {
+            let (first_start, first_end) = interval_ends(first);
+            let (second_start, second_end) = interval_ends(second);
+            if first_start.max(second_start) < first_end.min(second_end) - TOLERANCE {
+                errors.push(ValidationError::OverlappingBoundaries {
+                    branch: id,
+                    first: first.get_boundary(),
+                    second: second.get_boundary()
+                });
+            }
+        }
+    }
+
+    let mut covered_to = -1.0;
+    for connection in connections {
+        let (start, end) = interval_ends(connection);
+        if start > covered_to + TOLERANCE {
+            errors.push(ValidationError::BoundaryGap { branch: id, side, start: covered_to, end: start });
+        }
+        covered_to = f64::max(covered_to, end);
+    }
+    if covered_to < 1.0 - TOLERANCE {
+        errors.push(ValidationError::BoundaryGap { branch: id, side, start: covered_to, end: 1.0 });
+    }
+
+    errors
+}
+
+fn interval_ends(connection: &Connection) -> (f64, f64) {
+    match connection.get_boundary() {
+        Boundary::Left(start, end) | Boundary::Right(start, end) => (start, end)
+    }
+}
@@ -0,0 +1,125 @@
+// Binding actions to keys, buttons and stick directions, and saving the bindings
+extern crate tree_game;
+extern crate generic_game as gg;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::action_map::{ActionMap, ActionMapError, Action, Binding, BindingConflict, Key, StickAxis};
+use tree_game::tree_game::gamepad::{GamepadButton, GamepadState};
+use gg::input::KeyboardInput;
+use na::Vector2;
+use std::env;
+use std::fs;
+
+fn assert_same_bindings(actual: &ActionMap, expected: &ActionMap) {
+    for &action in Action::ALL.iter() {
+        assert_eq!(actual.get_bindings(action), expected.get_bindings(action), "bindings for {}", action.name());
+    }
+}
+
+#[test]
+fn binding_an_input_twice_is_a_conflict() {
+    let mut map = ActionMap::default();
+
+    let result = map.bind(Action::Pause, Binding::Key(Key::Space));
+
+    assert_eq!(result, Err(BindingConflict {
+        binding: Binding::Key(Key::Space),
+        existing: Action::JumpBranch,
+        requested: Action::Pause
+    }));
+    assert_eq!(map.get_action(Binding::Key(Key::Space)), Some(Action::JumpBranch), "the old binding is kept");
+    assert_eq!(map.get_bindings(Action::Pause), vec![Binding::Key(Key::Escape), Binding::Button(GamepadButton::Start)]);
+}
+
+#[test]
+fn binding_an_input_to_its_own_action_again_is_allowed() {
+    let mut map = ActionMap::default();
+    assert_eq!(map.bind(Action::JumpBranch, Binding::Key(Key::Space)), Ok(()));
+    assert_eq!(map.get_bindings(Action::JumpBranch), vec![Binding::Key(Key::Space), Binding::Button(GamepadButton::A)]);
+}
+
+#[test]
+fn rebinding_moves_an_input_between_actions() {
+    let mut map = ActionMap::default();
+
+    map.rebind(Action::Pause, Binding::Key(Key::Space));
+
+    assert_eq!(map.get_action(Binding::Key(Key::Space)), Some(Action::Pause));
+    assert_eq!(map.get_bindings(Action::JumpBranch), vec![Binding::Button(GamepadButton::A)]);
+
+    map.unbind(Binding::Key(Key::Space));
+    assert_eq!(map.get_action(Binding::Key(Key::Space)), None);
+}
+
+#[test]
+fn values_come_from_the_requested_device_only() {
+    let mut map = ActionMap::new();
+    map.bind(Action::MoveRight, Binding::Key(Key::D)).unwrap();
+    map.bind(Action::MoveRight, Binding::Axis(StickAxis::PositiveX)).unwrap();
+    let mut kbd = KeyboardInput::default();
+    Key::D.set_pressed(&mut kbd, true);
+    let gamepad = GamepadState {
+        stick: Vector2::new(0.25, 0.0),
+        buttons: [false; 5]
+    };
+
+    assert_eq!(map.get_value(Action::MoveRight, &kbd, &gamepad, true), 1.0);
+    assert_eq!(map.get_value(Action::MoveRight, &kbd, &gamepad, false), 0.25);
+    assert_eq!(map.get_value(Action::MoveLeft, &kbd, &gamepad, false), 0.0);
+}
+
+#[test]
+fn bindings_round_trip_through_the_config_format() {
+    let mut map = ActionMap::default();
+    map.rebind(Action::MoveLeft, Binding::Key(Key::A));
+    map.rebind(Action::Interact, Binding::Button(GamepadButton::B));
+    map.clear(Action::NextTheme);
+
+    let loaded = ActionMap::from_config_str(&map.to_config_string()).unwrap();
+
+    assert_same_bindings(&loaded, &map);
+    assert_eq!(loaded.to_config_string(), map.to_config_string());
+}
+
+#[test]
+fn bindings_round_trip_through_a_file() {
+    let path = env::temp_dir().join(format!("tree_game_bindings_{}.cfg", std::process::id()));
+    let mut map = ActionMap::default();
+    map.rebind(Action::JumpBranch, Binding::Key(Key::Z));
+
+    map.save(&path).unwrap();
+    let loaded = ActionMap::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_same_bindings(&loaded, &map);
+}
+
+#[test]
+fn conflicting_lines_are_reported_with_their_line() {
+    let contents = "# movement\nmove_left key a\n\nmove_right key a\n";
+
+    match ActionMap::from_config_str(contents) {
+        Err(ActionMapError::Conflict { line, conflict }) => {
+            assert_eq!(line, 4);
+            assert_eq!(conflict.existing, Action::MoveLeft);
+            assert_eq!(conflict.requested, Action::MoveRight);
+        },
+        other => panic!("expected a conflict, got {:?}", other)
+    }
+}
+
+#[test]
+fn unreadable_lines_are_reported_with_their_line() {
+    for &(contents, expected_line) in [
+        ("jump_branch key space\nfly key f\n", 2),
+        ("move_up key\n", 1),
+        ("move_up pedal w\n", 1),
+        ("pause key f\npause button select\n", 2),
+        ("move_up axis z+\n", 1)
+    ].iter() {
+        match ActionMap::from_config_str(contents) {
+            Err(ActionMapError::Parse { line, .. }) => assert_eq!(line, expected_line, "{:?}", contents),
+            other => panic!("expected a parse error for {:?}, got {:?}", contents, other)
+        }
+    }
+}
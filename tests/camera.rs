@@ -0,0 +1,68 @@
+// Following and zooming of the camera
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::camera::{Camera, CameraSettings};
+use na::Vector2;
+
+const EPSILON: f64 = 1e-9;
+const T_STEP: f64 = 1.0 / 60.0;
+
+fn new_camera() -> Camera {
+    Camera::new(CameraSettings::default(), Vector2::new(0.0, 0.0))
+}
+
+#[test]
+fn viewport_height_shrinks_with_each_layer() {
+    let camera = new_camera();
+    let settings = camera.settings;
+    assert!((camera.get_target_viewport_height(0) - settings.base_viewport_height).abs() < EPSILON);
+    for layer in 1..5 {
+        let expected = settings.base_viewport_height * settings.zoom_per_layer.powi(layer as i32);
+        assert!((camera.get_target_viewport_height(layer) - expected).abs() < EPSILON, "layer {}", layer);
+        assert!(camera.get_target_viewport_height(layer) < camera.get_target_viewport_height(layer - 1));
+    }
+}
+
+#[test]
+fn viewport_height_stops_at_the_minimum() {
+    let camera = new_camera();
+    assert_eq!(camera.get_target_viewport_height(100), camera.settings.min_viewport_height);
+}
+
+#[test]
+fn snap_to_jumps_straight_to_the_target() {
+    let mut camera = new_camera();
+    let target = Vector2::new(3.0, -2.0);
+    camera.snap_to(target, 2);
+    assert_eq!(camera.get_pos(), target);
+    assert_eq!(camera.get_viewport_height(), camera.get_target_viewport_height(2));
+}
+
+#[test]
+fn update_converges_on_a_still_target() {
+    let mut camera = new_camera();
+    let target = Vector2::new(5.0, 1.0);
+    let mut last_distance = (target - camera.get_pos()).norm();
+    for _ in 0..600 {
+        camera.update(target, 3, T_STEP);
+        let distance = (target - camera.get_pos()).norm();
+        assert!(distance <= last_distance + EPSILON, "the camera moved away from its target");
+        last_distance = distance;
+    }
+
+    // The target only has to end up inside the deadzone
+    let deadzone = camera.settings.deadzone * camera.get_viewport_height();
+    let offset = target - camera.get_pos();
+    assert!(offset.x.abs() <= deadzone.x + 1e-3 && offset.y.abs() <= deadzone.y + 1e-3, "offset {:?}", offset);
+    assert!((camera.get_viewport_height() - camera.get_target_viewport_height(3)).abs() < 1e-3);
+}
+
+#[test]
+fn update_ignores_movement_inside_the_deadzone() {
+    let mut camera = new_camera();
+    camera.snap_to(Vector2::new(0.0, 0.0), 0);
+    let inside = camera.settings.deadzone * camera.get_viewport_height() * 0.5;
+    camera.update(inside, 0, T_STEP);
+    assert_eq!(camera.get_pos(), Vector2::new(0.0, 0.0));
+}
@@ -0,0 +1,158 @@
+// Footprints of objects on the tree and the collisions between them
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::tree::{TreeData, TreeBranch, BranchId, Connection, Boundary};
+use tree_game::tree_game::tree::tree_branch::{BranchShape, VisualSpec, LogicalSpec};
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::movable::Movable;
+use tree_game::tree_game::collision::{Collidable, EntityId, CollisionEvent, overlaps, detect_collisions};
+use tree_game::tree_game::footprint::get_footprint;
+use na::{Vector2, Vector4};
+use std::collections::HashMap;
+
+const EPSILON: f64 = 1e-9;
+
+struct Block {
+    position: Position,
+    length: f64,
+    height: f64
+}
+
+impl Block {
+    fn new(id: BranchId, x: f64, y: f64, length: f64) -> Block {
+        Block {
+            position: Position::new(id, Vector2::new(x, y)),
+            length,
+            height: 0.1
+        }
+    }
+}
+
+impl Movable for Block {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+}
+
+impl Collidable for Block {
+    fn get_length(&self) -> f64 {
+        self.length
+    }
+
+    fn get_height(&self) -> f64 {
+        self.height
+    }
+}
+
+fn wide() -> BranchId {
+    BranchId::new(0, 0)
+}
+
+fn narrow() -> BranchId {
+    BranchId::new(0, 1)
+}
+
+// A wide branch leading into one half as wide, both a unit long
+fn build_tree() -> TreeData {
+    let branch = |id: BranchId, width: f64, connection: Connection| TreeBranch::from_parts(
+        id,
+        VisualSpec::from_shape(Vector2::new(id.layer as f64, 0.0), BranchShape { length: 1.0, width, rise: 0.0 }),
+        LogicalSpec::new_logical_rect(width, 1.0),
+        vec![connection]
+    );
+    let mut branches = HashMap::new();
+    branches.insert(wide(), branch(wide(), 1.0, Connection::new(narrow(), Boundary::Right(-1.0, 1.0))));
+    branches.insert(narrow(), branch(narrow(), 0.5, Connection::new(wide(), Boundary::Left(-1.0, 1.0))));
+    TreeData::new(branches, 1)
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn objects_inside_a_branch_cover_one_rect() {
+    let tree = build_tree();
+    let footprint = get_footprint(&tree, Position::new(wide(), Vector2::new(0.5, 0.2)), 0.3, 0.1, Vector4::new(1.0, 0.0, 0.0, 1.0)).unwrap();
+
+    assert_eq!(footprint.len(), 1);
+    assert_eq!(footprint[0].branch_id, wide());
+    assert_eq!(footprint[0].pos, Vector2::new(0.5, 0.2));
+    assert_eq!((footprint[0].length, footprint[0].height), (0.3, 0.1));
+}
+
+#[test]
+fn objects_over_the_end_of_a_branch_spill_onto_the_next() {
+    let tree = build_tree();
+    let footprint = get_footprint(&tree, Position::new(wide(), Vector2::new(0.95, 0.2)), 0.3, 0.1, Vector4::new(1.0, 0.0, 0.0, 1.0)).unwrap();
+
+    assert_eq!(footprint.len(), 2);
+    assert_eq!(footprint[0].branch_id, wide());
+    let spill = &footprint[1];
+    assert_eq!(spill.branch_id, narrow());
+    assert_close(spill.length, 0.1, "length past the end");
+    assert_close(spill.pos.x, 0.05, "x on the next branch");
+    assert_close(spill.pos.y, 0.1, "y scaled to the narrower branch");
+    assert_eq!(spill.height, 0.1);
+}
+
+#[test]
+fn objects_off_the_tree_have_no_footprint() {
+    let tree = build_tree();
+    let lost = Block::new(BranchId::new(3, 3), 0.5, 0.0, 0.3);
+    assert!(lost.get_collision_rects(&tree).is_empty());
+    assert!(!overlaps(&tree, &lost, &Block::new(wide(), 0.5, 0.0, 0.3)));
+}
+
+#[test]
+fn overlapping_objects_on_one_branch_collide() {
+    let tree = build_tree();
+    let first = Block::new(wide(), 0.5, 0.0, 0.3);
+    assert!(overlaps(&tree, &first, &Block::new(wide(), 0.7, 0.05, 0.2)));
+    assert!(overlaps(&tree, &first, &first));
+}
+
+#[test]
+fn objects_apart_on_one_branch_miss() {
+    let tree = build_tree();
+    let first = Block::new(wide(), 0.5, 0.0, 0.3);
+    // Just past touching along the branch, then across it
+    assert!(!overlaps(&tree, &first, &Block::new(wide(), 0.75, 0.0, 0.2)));
+    assert!(!overlaps(&tree, &first, &Block::new(wide(), 0.5, 0.1, 0.3)));
+}
+
+#[test]
+fn objects_collide_across_the_join_between_branches() {
+    let tree = build_tree();
+    let straddling = Block::new(wide(), 0.95, 0.2, 0.3);
+    let beyond_join = Block::new(narrow(), 0.12, 0.1, 0.1);
+    let further_on = Block::new(narrow(), 0.2, 0.1, 0.1);
+
+    assert!(overlaps(&tree, &straddling, &beyond_join));
+    assert!(overlaps(&tree, &beyond_join, &straddling));
+    assert!(!overlaps(&tree, &straddling, &further_on));
+}
+
+#[test]
+fn each_colliding_pair_is_reported_once() {
+    let tree = build_tree();
+    let player = Block::new(wide(), 0.95, 0.2, 0.3);
+    let enemy = Block::new(narrow(), 0.12, 0.1, 0.1);
+    let pickup = Block::new(wide(), 0.2, 0.0, 0.1);
+    let entities: Vec<(EntityId, &dyn Collidable)> = vec![
+        (EntityId::Player, &player),
+        (EntityId::Enemy(0), &enemy),
+        (EntityId::Pickup(0), &pickup)
+    ];
+
+    let events = detect_collisions(&tree, &entities);
+
+    assert_eq!(events, vec![CollisionEvent { first: EntityId::Player, second: EntityId::Enemy(0) }]);
+    assert_eq!(events[0].get_other(EntityId::Enemy(0)), Some(EntityId::Player));
+    assert_eq!(events[0].get_other(EntityId::Pickup(0)), None);
+}
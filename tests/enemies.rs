@@ -0,0 +1,107 @@
+// Enemies patrolling, chasing the player along the tree, and speeding up with the difficulty
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, Difficulty};
+use tree_game::tree_game::enemy::{Enemy, EnemyBehaviour};
+use tree_game::tree_game::headless::HeadlessRunner;
+use tree_game::tree_game::movable::Movable;
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBuilder, BranchId, EnemySpawn};
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+const EPSILON: f64 = 1e-9;
+const ENEMY_LENGTH: f64 = 0.3;
+const ENEMY_HEIGHT: f64 = 0.1;
+
+fn build_tree() -> TreeData {
+    TreeBuilder::new(3).build_tree()
+}
+
+fn middle_of(tree: &TreeData, id: BranchId) -> Position {
+    Position::new(id, Vector2::new(tree.get_branch(id).get_logical().length / 2.0, 0.0))
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn patrols_turn_around_at_the_ends_of_their_branch() {
+    let tree = build_tree();
+    let root = BranchId::new(0, 0);
+    let length = tree.get_branch(root).get_logical().length;
+    let speed = 1.0;
+    let mut enemy = Enemy::new(middle_of(&tree, root), ENEMY_LENGTH, ENEMY_HEIGHT, speed, EnemyBehaviour::Patrol, 1);
+    let player = middle_of(&tree, root);
+
+    let mut xs = vec![enemy.get_position().get_branch_position().x];
+    let steps = (3.0 * length / (speed * T_STEP)) as usize;
+    for _ in 0..steps {
+        enemy.update(&tree, player, T_STEP).unwrap();
+        assert_eq!(enemy.get_position().get_branch_id(), root, "patrols stay on their branch");
+        xs.push(enemy.get_position().get_branch_position().x);
+    }
+
+    let highest = xs.iter().cloned().fold(f64::MIN, f64::max);
+    let lowest = xs.iter().cloned().fold(f64::MAX, f64::min);
+    assert_close(highest, length - ENEMY_LENGTH / 2.0, "turning point at the right end");
+    assert_close(lowest, ENEMY_LENGTH / 2.0, "turning point at the left end");
+
+    // Right after the first turn the enemy heads back the way it came at full speed
+    let turn = (0..xs.len() - 1).find(|&i| xs[i + 1] < xs[i]).unwrap();
+    assert_close(xs[turn], highest, "where the enemy turned");
+    assert_close(xs[turn + 1], highest - speed * T_STEP, "first step back");
+}
+
+#[test]
+fn chasers_follow_the_path_to_the_player() {
+    let tree = build_tree();
+    let start = middle_of(&tree, BranchId::new(0, 0));
+    let player = middle_of(&tree, BranchId::new(1, 1));
+    let path = tree.get_path(start.get_branch_id(), player.get_branch_id()).unwrap().unwrap();
+    assert!(path.len() > 2, "the player should be a few branches away");
+    let mut enemy = Enemy::new(start, ENEMY_LENGTH, ENEMY_HEIGHT, 0.3, EnemyBehaviour::Chase, 1);
+    let distance_to_player = |enemy: &Enemy| (tree.get_world_position(enemy.get_position()) - tree.get_world_position(player)).norm();
+    let start_distance = distance_to_player(&enemy);
+
+    let mut path_index = 0;
+    for _ in 0..600 {
+        enemy.update(&tree, player, T_STEP).unwrap();
+        let id = enemy.get_position().get_branch_id();
+        let index = path.iter().position(|&step| step == id).expect("the chaser left the path");
+        assert!(index >= path_index, "the chaser went back from {:?} to {:?}", path[path_index], id);
+        path_index = index;
+    }
+
+    assert_eq!(enemy.get_position().get_branch_id(), player.get_branch_id());
+    assert!(distance_to_player(&enemy) < EPSILON, "the chaser stopped {} short", distance_to_player(&enemy));
+    assert!(start_distance > 0.1);
+}
+
+// Runs a single frame with one patrol and returns how far it moved
+fn patrol_step(difficulty: Difficulty) -> f64 {
+    let mut level = build_tree();
+    let spawn = middle_of(&level, BranchId::new(1, 1));
+    level.add_enemy(EnemySpawn::new(EnemyBehaviour::Patrol, spawn));
+    let setup = GameSetup {
+        difficulty,
+        ..GameSetup::default()
+    };
+    let mut runner = HeadlessRunner::new(TreeGame::from_level(setup, level).unwrap(), T_STEP);
+
+    runner.step(&Default::default());
+
+    runner.game.enemies[0].get_position().get_branch_position().x - spawn.get_branch_position().x
+}
+
+#[test]
+fn difficulty_scales_enemy_speed() {
+    let base_speed = 0.5;
+    for &difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard].iter() {
+        let expected = base_speed * difficulty.get_enemy_speed_scale() * T_STEP;
+        assert_close(patrol_step(difficulty), expected, difficulty.name());
+    }
+    assert!(patrol_step(Difficulty::Hard) > patrol_step(Difficulty::Easy));
+}
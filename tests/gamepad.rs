@@ -0,0 +1,128 @@
+// Stick deadzone and response curve, and switching control between the keyboard and a gamepad
+extern crate tree_game;
+extern crate generic_game as gg;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, GamePhase, ExternalInput};
+use tree_game::tree_game::gamepad::{GamepadState, StickSettings, InputDevice};
+use tree_game::tree_game::headless::HeadlessRunner;
+use tree_game::tree_game::physics::Physical;
+use gg::input::JoystickInput;
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+const EPSILON: f64 = 1e-12;
+
+fn stick_settings() -> StickSettings {
+    StickSettings {
+        deadzone: 0.2,
+        response_exponent: 1.5
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn deflections_inside_the_deadzone_are_ignored() {
+    let settings = stick_settings();
+    assert_eq!(settings.apply(Vector2::new(0.0, 0.0)), Vector2::new(0.0, 0.0));
+    assert_eq!(settings.apply(Vector2::new(0.12, -0.16)), Vector2::new(0.0, 0.0));
+    assert_eq!(settings.apply(Vector2::new(0.2, 0.0)), Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn response_curve_starts_from_the_edge_of_the_deadzone() {
+    let settings = stick_settings();
+    // Three fifths of the way out is half way between the deadzone and the edge
+    let output = settings.apply(Vector2::new(0.36, 0.48));
+    assert_close(output.norm(), 0.5f64.powf(1.5), "strength");
+    assert_close(output.x / output.y, 0.75, "direction");
+
+    assert_close(settings.apply(Vector2::new(0.0, -1.0)).y, -1.0, "full deflection");
+    assert_close(settings.apply(Vector2::new(0.3, 0.0)).x, 0.125f64.powf(1.5), "just outside the deadzone");
+}
+
+#[test]
+fn linear_response_rescales_past_the_deadzone() {
+    let settings = StickSettings { deadzone: 0.25, response_exponent: 1.0 };
+    assert_close(settings.apply(Vector2::new(-0.625, 0.0)).x, -0.5, "half way");
+}
+
+#[test]
+fn deflections_past_the_edge_are_capped() {
+    let output = stick_settings().apply(Vector2::new(3.0, 4.0));
+    assert_close(output.norm(), 1.0, "strength");
+    assert_close(output.x, 0.6, "x");
+}
+
+#[test]
+fn gamepad_state_round_trips_through_joystick_input() {
+    let state = GamepadState {
+        stick: Vector2::new(-0.4, 0.9),
+        buttons: [true, false, true, false, true]
+    };
+    let mut joystick = JoystickInput::default();
+    state.apply_to(&mut joystick);
+    assert_eq!(GamepadState::from(&joystick), state);
+}
+
+fn new_runner() -> HeadlessRunner {
+    HeadlessRunner::new(TreeGame::new(GameSetup::default()).unwrap(), T_STEP)
+}
+
+fn input(right_key: bool, stick_x: f64) -> ExternalInput {
+    let mut input = ExternalInput::default();
+    input.kbd.right = right_key;
+    input.gamepad.left_x = stick_x;
+    input
+}
+
+// Velocity picked up in one step from rest with the given movement input
+fn velocity_for(movement: f64) -> f64 {
+    movement * GameSetup::default().player_acceleration * T_STEP
+}
+
+#[test]
+fn touching_the_stick_hands_control_to_the_gamepad() {
+    let mut runner = new_runner();
+    assert_eq!(runner.game.state.phase, GamePhase::Playing);
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Keyboard);
+
+    runner.step_input(&input(false, 0.6), T_STEP);
+
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Gamepad);
+    assert_close(runner.game.player.get_body().velocity.x, velocity_for(0.5f64.powf(1.5)), "velocity from the stick");
+}
+
+#[test]
+fn pressing_a_key_hands_control_back_to_the_keyboard() {
+    let mut runner = new_runner();
+    runner.step_input(&input(false, 0.6), T_STEP);
+    runner.step_input(&ExternalInput::default(), T_STEP);
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Gamepad, "letting go keeps the device");
+
+    runner.step_input(&input(true, 0.0), T_STEP);
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Keyboard);
+}
+
+#[test]
+fn the_device_in_control_wins_when_both_are_used() {
+    let mut runner = new_runner();
+    runner.step_input(&input(false, -1.0), T_STEP);
+    runner.game.player.get_body_mut().velocity = Vector2::new(0.0, 0.0);
+
+    runner.step_input(&input(true, -1.0), T_STEP);
+
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Gamepad);
+    assert_close(runner.game.player.get_body().velocity.x, velocity_for(-1.0), "velocity from the stick");
+}
+
+#[test]
+fn sticks_inside_the_deadzone_do_not_take_control() {
+    let mut runner = new_runner();
+    runner.step_input(&input(false, 0.15), T_STEP);
+    assert_eq!(runner.game.input_keys.active_device, InputDevice::Keyboard);
+    assert_eq!(runner.game.player.get_body().velocity.x, 0.0);
+}
@@ -0,0 +1,53 @@
+// Software renders of a fixed-seed game checked against the reference images in tests/golden.
+// Set UPDATE_GOLDEN=1 to rewrite the references after an intended change to the drawing.
+extern crate tree_game;
+
+use tree_game::tree_game::{TreeGame, GameSetup};
+use tree_game::tree_game::image::Image;
+use tree_game::tree_game::raster::RasterView;
+use std::env;
+use std::path::PathBuf;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+const CHANNEL_TOLERANCE: u8 = 2;
+const MAX_MISMATCHED_FRACTION: f64 = 0.001;
+
+fn new_game() -> TreeGame {
+    let setup = GameSetup {
+        num_layers: 4,
+        seed: Some(7),
+        ..GameSetup::default()
+    };
+    TreeGame::new(setup).unwrap()
+}
+
+fn check_golden(name: &str, image: &Image) {
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.png", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        image.save_png(&golden_path).unwrap();
+        return;
+    }
+
+    let golden = Image::load_png(&golden_path).unwrap();
+    let diff = image.compare(&golden, CHANNEL_TOLERANCE);
+    if !diff.passes(MAX_MISMATCHED_FRACTION) {
+        let diff_path = env::temp_dir().join(format!("{}.diff.png", name));
+        image.get_difference_image(&golden, CHANNEL_TOLERANCE).save_png(&diff_path).unwrap();
+        panic!("{} does not match {}: {} (differences drawn to {})", name, golden_path.display(), diff, diff_path.display());
+    }
+}
+
+#[test]
+fn whole_tree_matches_golden() {
+    let game = new_game();
+    let view = RasterView::fit_tree(&game.tree, WIDTH as f64 / HEIGHT as f64);
+    check_golden("tree", &game.snapshot(WIDTH, HEIGHT, view));
+}
+
+#[test]
+fn camera_view_matches_golden() {
+    let game = new_game();
+    let view = RasterView::from_camera(&game.camera);
+    check_golden("camera", &game.snapshot(WIDTH, HEIGHT, view));
+}
@@ -0,0 +1,79 @@
+// Driving a TreeGame without a window through the HeadlessRunner
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, GamePhase, SetupError};
+use tree_game::tree_game::headless::{HeadlessRunner, ScriptStep};
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::tree::BranchId;
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+
+fn new_game() -> TreeGame {
+    TreeGame::new(GameSetup::default()).unwrap()
+}
+
+#[test]
+fn runner_starts_playing() {
+    let runner = HeadlessRunner::new(new_game(), T_STEP);
+    assert_eq!(runner.game.state.phase, GamePhase::Playing);
+}
+
+#[test]
+fn moving_right_moves_the_player() {
+    let mut runner = HeadlessRunner::new(new_game(), T_STEP);
+    let start = runner.get_report().final_position;
+    let report = runner.run_script(&[ScriptStep::moving(30, Vector2::new(1.0, 0.0))]);
+    assert_eq!(report.frames, 30);
+    assert!(report.final_velocity.x > 0.0);
+    let moved = report.final_position.get_branch_id() != start.get_branch_id()
+        || report.final_position.get_branch_position().x > start.get_branch_position().x;
+    assert!(moved, "the player stayed at {:?}", report.final_position);
+}
+
+#[test]
+fn players_on_the_title_screen_do_not_move() {
+    let mut runner = HeadlessRunner::with_phase(new_game(), T_STEP, GamePhase::Title);
+    let start = runner.get_report().final_position;
+    let report = runner.run_script(&[ScriptStep::moving(30, Vector2::new(1.0, 0.0))]);
+    assert_eq!(report.final_position.get_branch_position(), start.get_branch_position());
+}
+
+#[test]
+fn idle_players_stay_still() {
+    let mut runner = HeadlessRunner::new(new_game(), T_STEP);
+    let start = runner.get_report().final_position;
+    let report = runner.run_script(&[ScriptStep::idle(60)]);
+    assert_eq!(report.final_position.get_branch_id(), start.get_branch_id());
+    assert_eq!(report.final_position.get_branch_position(), start.get_branch_position());
+    assert_eq!(report.final_velocity, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn report_covers_every_frame() {
+    let mut runner = HeadlessRunner::new(new_game(), T_STEP);
+    let report = runner.run_script(&[ScriptStep::idle(10), ScriptStep::moving(15, Vector2::new(1.0, 0.0))]);
+    assert_eq!(report.frames, 25);
+    assert_eq!(report.trajectory.len(), 25);
+    assert!((report.elapsed - 25.0 * T_STEP).abs() < 1e-9);
+    for (index, record) in report.trajectory.iter().enumerate() {
+        assert_eq!(record.frame, index + 1);
+    }
+    assert!((report.state.survival_time - report.elapsed).abs() < 1e-9);
+}
+
+#[test]
+fn starting_off_the_tree_is_an_error() {
+    let setup = GameSetup {
+        player_start: Position::new(BranchId::new(40, 3), Vector2::new(0.1, 0.0)),
+        ..GameSetup::default()
+    };
+    assert!(matches!(TreeGame::new(setup), Err(SetupError::Invalid(_))));
+
+    let setup = GameSetup {
+        player_start: Position::new(BranchId::new(0, 0), Vector2::new(0.1, 5.0)),
+        ..GameSetup::default()
+    };
+    assert!(TreeGame::new(setup).is_err());
+}
@@ -0,0 +1,80 @@
+// Comparing images and writing them to and from PNG files
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::image::Image;
+use na::Vector4;
+use std::env;
+use std::fs;
+
+fn new_image() -> Image {
+    Image::new(4, 3, Vector4::new(0.2, 0.4, 0.6, 1.0))
+}
+
+#[test]
+fn identical_images_match() {
+    let diff = new_image().compare(&new_image(), 0);
+    assert!(diff.size_matches);
+    assert_eq!(diff.total_pixels, 12);
+    assert_eq!(diff.mismatched_pixels, 0);
+    assert_eq!(diff.max_difference, 0);
+    assert!(diff.passes(0.0));
+}
+
+#[test]
+fn differences_within_the_tolerance_are_allowed() {
+    let mut image = new_image();
+    let pixel = image.get_pixel(1, 1);
+    image.set_pixel(1, 1, [pixel[0] + 2, pixel[1], pixel[2], pixel[3]]);
+
+    let diff = image.compare(&new_image(), 2);
+    assert_eq!(diff.mismatched_pixels, 0);
+    assert_eq!(diff.max_difference, 2);
+    assert!(diff.passes(0.0));
+}
+
+#[test]
+fn differences_beyond_the_tolerance_are_counted() {
+    let mut image = new_image();
+    image.set_pixel(0, 0, [255, 0, 0, 255]);
+    image.set_pixel(3, 2, [255, 0, 0, 255]);
+
+    let diff = image.compare(&new_image(), 2);
+    assert_eq!(diff.mismatched_pixels, 2);
+    assert!(diff.max_difference > 2);
+    assert!(!diff.passes(0.0));
+    assert!(!diff.passes(0.1));
+    assert!(diff.passes(2.0 / 12.0));
+}
+
+#[test]
+fn images_of_different_sizes_do_not_match() {
+    let other = Image::new(3, 4, Vector4::new(0.2, 0.4, 0.6, 1.0));
+    let diff = new_image().compare(&other, 255);
+    assert!(!diff.size_matches);
+    assert!(!diff.passes(1.0));
+}
+
+#[test]
+fn difference_image_marks_mismatched_pixels() {
+    let mut image = new_image();
+    image.set_pixel(2, 1, [0, 0, 0, 255]);
+    let difference = image.get_difference_image(&new_image(), 0);
+    assert_eq!(difference.get_pixel(2, 1), [255, 0, 0, 255]);
+    assert_ne!(difference.get_pixel(0, 0), [255, 0, 0, 255]);
+}
+
+#[test]
+fn png_round_trip_keeps_every_pixel() {
+    let mut image = new_image();
+    image.set_pixel(0, 0, [255, 0, 0, 255]);
+    image.set_pixel(3, 2, [10, 20, 30, 128]);
+    image.blend_pixel(1, 2, Vector4::new(1.0, 1.0, 0.0, 0.5));
+
+    let path = env::temp_dir().join(format!("tree_game_round_trip_{}.png", std::process::id()));
+    image.save_png(&path).unwrap();
+    let loaded = Image::load_png(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), image);
+}
@@ -0,0 +1,171 @@
+// Saving a level and loading it back should give the same tree
+extern crate tree_game;
+
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBuilder, RandomSpec, BranchId, Boundary, LevelError, ValidationError};
+
+const EPSILON: f64 = 1e-9;
+
+fn build_level(seed: u64) -> TreeData {
+    TreeBuilder::new_random(4, RandomSpec::new(seed))
+        .with_pickups(1.0, seed)
+        .build_tree()
+}
+
+fn round_trip(tree: &TreeData) -> TreeData {
+    TreeData::from_level_str(&tree.to_level_string()).expect("the saved level should load")
+}
+
+fn sorted_ids(tree: &TreeData) -> Vec<BranchId> {
+    let mut ids: Vec<BranchId> = tree.get_branches().keys().cloned().collect();
+    ids.sort_by_key(|id| (id.layer, id.id));
+    ids
+}
+
+fn boundary_parts(boundary: Boundary) -> (bool, f64, f64) {
+    match boundary {
+        Boundary::Left(start, end) => (false, start, end),
+        Boundary::Right(start, end) => (true, start, end)
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn round_trip_keeps_branch_ids() {
+    let tree = build_level(3);
+    let loaded = round_trip(&tree);
+    assert_eq!(sorted_ids(&loaded), sorted_ids(&tree));
+    assert_eq!(loaded.get_max_depth(), tree.get_max_depth());
+}
+
+#[test]
+fn round_trip_keeps_connections() {
+    let tree = build_level(3);
+    let loaded = round_trip(&tree);
+    for id in sorted_ids(&tree) {
+        let expected = tree.get_branch(id).get_connections();
+        let actual = loaded.get_branch(id).get_connections();
+        assert_eq!(actual.len(), expected.len(), "connection count of {:?}", id);
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_eq!(actual.get_branch_id(), expected.get_branch_id());
+            let (actual_right, actual_start, actual_end) = boundary_parts(actual.get_boundary());
+            let (expected_right, expected_start, expected_end) = boundary_parts(expected.get_boundary());
+            assert_eq!(actual_right, expected_right, "side of connection from {:?}", id);
+            assert_close(actual_start, expected_start, "connection start");
+            assert_close(actual_end, expected_end, "connection end");
+        }
+    }
+}
+
+#[test]
+fn round_trip_keeps_logical_specs() {
+    let tree = build_level(8);
+    let loaded = round_trip(&tree);
+    for id in sorted_ids(&tree) {
+        let expected = tree.get_branch(id).get_logical();
+        let actual = loaded.get_branch(id).get_logical();
+        assert_close(actual.left_width, expected.left_width, "left width");
+        assert_close(actual.right_width, expected.right_width, "right width");
+        assert_close(actual.length, expected.length, "length");
+    }
+}
+
+#[test]
+fn round_trip_keeps_visual_control_points() {
+    let tree = build_level(8);
+    let loaded = round_trip(&tree);
+    for id in sorted_ids(&tree) {
+        let expected = tree.get_branch(id).get_visual();
+        let actual = loaded.get_branch(id).get_visual();
+        let (expected_points, actual_points) = (expected.get_control_points(), actual.get_control_points());
+        for &(actual, expected) in &[
+            (actual_points.0, expected_points.0),
+            (actual_points.1, expected_points.1),
+            (actual_points.2, expected_points.2)
+        ] {
+            assert_close(actual.x, expected.x, "control point x");
+            assert_close(actual.y, expected.y, "control point y");
+        }
+        assert_close(actual.patch.width, expected.patch.width, "patch width");
+        assert_close(actual.patch.pos.x, expected.patch.pos.x, "patch x");
+        assert_close(actual.patch.pos.y, expected.patch.pos.y, "patch y");
+    }
+}
+
+#[test]
+fn round_trip_keeps_pickups() {
+    let tree = build_level(5);
+    assert!(!tree.get_pickups().is_empty(), "the test level should have pickups");
+    let loaded = round_trip(&tree);
+    assert_eq!(loaded.get_pickups().len(), tree.get_pickups().len());
+    for (actual, expected) in loaded.get_pickups().iter().zip(tree.get_pickups()) {
+        assert_eq!(actual.kind, expected.kind);
+        assert_eq!(actual.position.get_branch_id(), expected.position.get_branch_id());
+        assert_close(actual.position.get_branch_position().x, expected.position.get_branch_position().x, "pickup x");
+        assert_close(actual.position.get_branch_position().y, expected.position.get_branch_position().y, "pickup y");
+    }
+}
+
+#[test]
+fn saving_a_loaded_level_gives_the_same_file() {
+    let tree = build_level(11);
+    let saved = tree.to_level_string();
+    assert_eq!(round_trip(&tree).to_level_string(), saved);
+}
+
+#[test]
+fn round_trip_keeps_enemies() {
+    let tree = TreeBuilder::new_random(4, RandomSpec::new(5))
+        .with_enemies(3, 5)
+        .build_tree();
+    assert_eq!(tree.get_enemies().len(), 3);
+    let loaded = round_trip(&tree);
+    assert_eq!(loaded.get_enemies().len(), tree.get_enemies().len());
+    for (actual, expected) in loaded.get_enemies().iter().zip(tree.get_enemies()) {
+        assert_eq!(actual.behaviour, expected.behaviour);
+        assert_eq!(actual.position.get_branch_id(), expected.position.get_branch_id());
+        assert_close(actual.position.get_branch_position().x, expected.position.get_branch_position().x, "enemy x");
+    }
+}
+
+#[test]
+fn pickups_on_missing_branches_are_rejected() {
+    let level = build_level(3).to_level_string() + "pickup fruit 99 9 0.5 0\n";
+    match TreeData::from_level_str(&level) {
+        Err(LevelError::Invalid(errors)) => assert!(errors.iter().any(|error| match *error {
+            ValidationError::DanglingPickup { branch } => branch == BranchId::new(99, 9),
+            _ => false
+        })),
+        Err(err) => panic!("expected an invalid level but got {}", err),
+        Ok(_) => panic!("the level should not load")
+    }
+}
+
+#[test]
+fn enemies_on_missing_branches_are_rejected() {
+    let level = build_level(3).to_level_string() + "enemy chase 99 9 0.5 0\n";
+    assert!(matches!(TreeData::from_level_str(&level), Err(LevelError::Invalid(_))));
+}
+
+#[test]
+fn non_finite_numbers_are_rejected_with_their_line() {
+    let level = build_level(3).to_level_string();
+    for &(key, bad_value) in [("width", "NaN"), ("pos", "inf"), ("control", "-inf"), ("connection", "NaN"), ("pickup", "inf")].iter() {
+        let mut lines: Vec<String> = level.lines().map(|line| line.to_string()).collect();
+        let index = lines.iter().position(|line| line.starts_with(key)).unwrap();
+        let mut words: Vec<String> = lines[index].split_whitespace().map(|word| word.to_string()).collect();
+        *words.last_mut().unwrap() = bad_value.to_string();
+        lines[index] = words.join(" ");
+
+        match TreeData::from_level_str(&lines.join("\n")) {
+            Err(LevelError::Parse { line, message }) => {
+                assert_eq!(line, index + 1, "{}", key);
+                assert!(message.contains(bad_value), "{}: {}", key, message);
+            },
+            Err(err) => panic!("expected a parse error for {} but got {}", key, err),
+            Ok(_) => panic!("{} {} should not load", key, bad_value)
+        }
+    }
+}
@@ -0,0 +1,229 @@
+// Moving along a fixed-seed tree, across the joins between trunks and branches
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBranch, TreeBuilder, RandomSpec, BranchId, Connection, Boundary};
+use tree_game::tree_game::tree::tree_branch::{BranchShape, VisualSpec, LogicalSpec};
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::movable::{Movable, MAX_BRANCH_CROSSINGS};
+use na::Vector2;
+use std::collections::HashMap;
+
+const SEED: u64 = 7;
+const EPSILON: f64 = 1e-9;
+
+struct Marker {
+    position: Position,
+    half_height: f64
+}
+
+impl Marker {
+    fn at(position: Position) -> Marker {
+        Marker {
+            position,
+            half_height: 0.0
+        }
+    }
+}
+
+impl Movable for Marker {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    fn get_half_height(&self) -> f64 {
+        self.half_height
+    }
+}
+
+fn build_tree() -> TreeData {
+    TreeBuilder::new_random(4, RandomSpec::new(SEED)).build_tree()
+}
+
+// A quarter of the way up the root, so the marker never sits exactly on the join between two branches
+fn start_on_root(tree: &TreeData) -> Marker {
+    let root = tree.get_root().get_logical();
+    let x = root.length / 2.0;
+    Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(x, root.get_half_width(x) / 2.0)))
+}
+
+fn move_by(marker: &mut Marker, tree: &TreeData, dx: f64) {
+    let report = marker.change_position(tree, Vector2::new(dx, 0.0)).expect("the tree should be well formed");
+    assert!(!report.blocked, "the marker was blocked at {:?}", marker.get_position());
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn moving_right_off_the_root_enters_a_branch() {
+    let tree = build_tree();
+    let mut marker = start_on_root(&tree);
+    let root_length = tree.get_root().get_logical().length;
+
+    move_by(&mut marker, &tree, root_length / 2.0 + 0.01);
+
+    let id = marker.get_position().get_branch_id();
+    assert_eq!(id.layer, 0, "branches stay on the layer of the trunk they split from");
+    assert_eq!(tree.get_parent(id).unwrap(), Some(BranchId::new(0, 0)));
+    assert_close(marker.get_position().get_branch_position().x, 0.01, "distance into the branch");
+}
+
+#[test]
+fn moving_through_a_branch_reaches_the_next_trunk() {
+    let tree = build_tree();
+    let mut marker = start_on_root(&tree);
+    let root_length = tree.get_root().get_logical().length;
+    move_by(&mut marker, &tree, root_length / 2.0 + 0.01);
+    let branch = marker.get_position().get_branch_id();
+    let branch_length = tree.get_branch(branch).get_logical().length;
+
+    move_by(&mut marker, &tree, branch_length);
+
+    let trunk = marker.get_position().get_branch_id();
+    assert_eq!(trunk.layer, 1);
+    assert_eq!(tree.get_parent(trunk).unwrap(), Some(branch));
+    assert_close(marker.get_position().get_branch_position().x, 0.01, "distance into the trunk");
+    let y = marker.get_position().get_branch_position().y;
+    assert!(y.abs() <= tree.get_branch(trunk).get_logical().get_half_width(0.01), "the marker left the trunk at y = {}", y);
+}
+
+#[test]
+fn moving_back_returns_to_the_same_place_on_the_root() {
+    let tree = build_tree();
+    let mut marker = start_on_root(&tree);
+    let start = marker.get_position();
+    let root_length = tree.get_root().get_logical().length;
+    move_by(&mut marker, &tree, root_length / 2.0 + 0.01);
+    let branch = marker.get_position().get_branch_id();
+    let branch_length = tree.get_branch(branch).get_logical().length;
+    move_by(&mut marker, &tree, branch_length);
+
+    move_by(&mut marker, &tree, -branch_length);
+    assert_eq!(marker.get_position().get_branch_id(), branch);
+
+    move_by(&mut marker, &tree, -(root_length / 2.0 + 0.01));
+    let end = marker.get_position();
+    assert_eq!(end.get_branch_id(), start.get_branch_id());
+    assert_close(end.get_branch_position().x, start.get_branch_position().x, "x on the root");
+    assert_close(end.get_branch_position().y, start.get_branch_position().y, "y on the root");
+}
+
+#[test]
+fn one_large_move_crosses_several_branches() {
+    let tree = TreeBuilder::new(3).build_tree();
+    let length = |layer: usize, id: usize| tree.get_branch(BranchId::new(id, layer)).get_logical().length;
+    let root_length = length(0, 0);
+    // Above the middle of the root, so the marker keeps to the top branch of each split
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(root_length / 2.0, 0.3)));
+
+    let dx = root_length / 2.0 + length(0, 1) + length(1, 0) + 0.01;
+    let report = marker.change_position(&tree, Vector2::new(dx, 0.0)).unwrap();
+
+    assert_eq!(report.branches_crossed, 3);
+    assert!(!report.blocked);
+    assert_eq!(marker.get_position().get_branch_id(), BranchId::new(1, 1));
+    assert_close(marker.get_position().get_branch_position().x, 0.01, "distance into the last branch");
+}
+
+// Two tiny branches joined end to end in a loop, so moving right never runs out of tree
+fn build_loop() -> TreeData {
+    let (first, second) = (BranchId::new(0, 0), BranchId::new(1, 0));
+    let shape = BranchShape { length: 0.001, width: 1.0, rise: 0.0 };
+    let branch = |id: BranchId, other: BranchId| TreeBranch::from_parts(
+        id,
+        VisualSpec::from_shape(Vector2::new(0.0, 0.0), shape),
+        LogicalSpec::new_logical_rect(1.0, shape.length),
+        vec![Connection::new(other, Boundary::Left(-1.0, 1.0)), Connection::new(other, Boundary::Right(-1.0, 1.0))]
+    );
+    let mut branches = HashMap::new();
+    branches.insert(first, branch(first, second));
+    branches.insert(second, branch(second, first));
+    TreeData::new(branches, 0)
+}
+
+#[test]
+fn moves_stop_after_too_many_crossings() {
+    let tree = build_loop();
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(0.0005, 0.0)));
+
+    let report = marker.change_position(&tree, Vector2::new(1.0, 0.0)).unwrap();
+
+    assert!(report.blocked);
+    assert_eq!(report.branches_crossed, MAX_BRANCH_CROSSINGS);
+    let x = marker.get_position().get_branch_position().x;
+    assert!((0.0..=0.001).contains(&x), "the marker stopped off its branch at x = {}", x);
+}
+
+// A wide branch leading into one half as wide, both a unit long
+fn build_narrowing() -> TreeData {
+    let (wide, narrow) = (BranchId::new(0, 0), BranchId::new(0, 1));
+    let branch = |id: BranchId, width: f64, connection: Connection| TreeBranch::from_parts(
+        id,
+        VisualSpec::from_shape(Vector2::new(id.layer as f64, 0.0), BranchShape { length: 1.0, width, rise: 0.0 }),
+        LogicalSpec::new_logical_rect(width, 1.0),
+        vec![connection]
+    );
+    let mut branches = HashMap::new();
+    branches.insert(wide, branch(wide, 1.0, Connection::new(narrow, Boundary::Right(-1.0, 1.0))));
+    branches.insert(narrow, branch(narrow, 0.5, Connection::new(wide, Boundary::Left(-1.0, 1.0))));
+    TreeData::new(branches, 1)
+}
+
+#[test]
+fn walls_stop_the_edge_of_a_tall_object() {
+    let tree = build_narrowing();
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(0.5, 0.0)));
+    marker.half_height = 0.1;
+
+    let report = marker.change_position(&tree, Vector2::new(0.0, 1.0)).unwrap();
+    assert!(report.hit_wall);
+    assert_close(marker.get_position().get_branch_position().y, 0.5 - 0.1, "y against the top wall");
+
+    let report = marker.change_position(&tree, Vector2::new(0.0, -2.0)).unwrap();
+    assert!(report.hit_wall);
+    assert_close(marker.get_position().get_branch_position().y, -(0.5 - 0.1), "y against the bottom wall");
+}
+
+#[test]
+fn objects_taller_than_the_branch_are_held_on_the_centre_line() {
+    let tree = build_narrowing();
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(0.5, 0.0)));
+    marker.half_height = 0.8;
+
+    let report = marker.change_position(&tree, Vector2::new(0.0, 0.3)).unwrap();
+
+    assert!(report.hit_wall);
+    assert_eq!(marker.get_position().get_branch_position().y, 0.0);
+}
+
+#[test]
+fn moves_inside_the_walls_do_not_hit_them() {
+    let tree = build_narrowing();
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(0.5, 0.0)));
+    marker.half_height = 0.1;
+
+    let report = marker.change_position(&tree, Vector2::new(0.1, 0.3)).unwrap();
+
+    assert!(!report.hit_wall);
+    assert_close(marker.get_position().get_branch_position().y, 0.3, "y");
+}
+
+#[test]
+fn crossing_into_a_narrower_branch_scales_vertical_positions() {
+    let tree = build_narrowing();
+    let mut marker = Marker::at(Position::new(BranchId::new(0, 0), Vector2::new(0.9, 0.2)));
+
+    let report = marker.change_position(&tree, Vector2::new(0.2, 0.0)).unwrap();
+
+    assert_eq!(report.branches_crossed, 1);
+    assert_close(report.vertical_scale, 0.5, "vertical scale");
+    assert_eq!(marker.get_position().get_branch_id(), BranchId::new(0, 1));
+    assert_close(marker.get_position().get_branch_position().x, 0.1, "x in the narrow branch");
+    assert_close(marker.get_position().get_branch_position().y, 0.1, "y in the narrow branch");
+}
@@ -0,0 +1,121 @@
+// Velocity, friction, knockback and wall responses of a PhysicsBody
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::physics::{PhysicsBody, WallResponse};
+use tree_game::tree_game::movable::MoveReport;
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+const EPSILON: f64 = 1e-12;
+
+fn moving_body(velocity: Vector2<f64>) -> PhysicsBody {
+    let mut body = PhysicsBody::new(8.0, 1.0, 8.0);
+    body.velocity = velocity;
+    body
+}
+
+fn report(vertical_scale: f64, blocked: bool, hit_wall: bool) -> MoveReport {
+    MoveReport {
+        branches_crossed: 0,
+        vertical_scale,
+        blocked,
+        hit_wall
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!((actual - expected).abs() < EPSILON, "{}: {} != {}", what, actual, expected);
+}
+
+#[test]
+fn friction_slows_a_body_without_input() {
+    let mut body = moving_body(Vector2::new(0.6, -0.3));
+    for _ in 0..10 {
+        body.step(Vector2::new(0.0, 0.0), T_STEP);
+    }
+    let decay = (1.0 - 8.0 * T_STEP).powi(10);
+    assert_close(body.velocity.x, 0.6 * decay, "x velocity");
+    assert_close(body.velocity.y, -0.3 * decay, "y velocity");
+}
+
+#[test]
+fn friction_stops_a_body_in_one_long_step() {
+    let mut body = moving_body(Vector2::new(0.6, -0.3));
+    let displacement = body.step(Vector2::new(0.0, 0.0), 0.2);
+    assert_eq!(body.velocity, Vector2::new(0.0, 0.0));
+    assert_eq!(displacement, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn friction_only_acts_on_axes_without_input() {
+    let mut body = moving_body(Vector2::new(0.5, 0.5));
+    body.step(Vector2::new(1.0, 0.0), T_STEP);
+    assert_close(body.velocity.x, 0.5 + 8.0 * T_STEP, "x velocity");
+    assert_close(body.velocity.y, 0.5 * (1.0 - 8.0 * T_STEP), "y velocity");
+}
+
+#[test]
+fn speed_is_capped_at_the_max_speed() {
+    let mut body = moving_body(Vector2::new(0.0, 0.0));
+    let mut displacement = Vector2::new(0.0, 0.0);
+    for _ in 0..60 {
+        displacement = body.step(Vector2::new(1.0, 1.0), T_STEP);
+    }
+    assert_close(body.velocity.norm(), 1.0, "speed");
+    assert_close(body.velocity.x, body.velocity.y, "direction");
+    assert_close(displacement.norm(), T_STEP, "distance in the last step");
+}
+
+#[test]
+fn knockback_outruns_the_max_speed_until_friction_catches_up() {
+    let mut body = moving_body(Vector2::new(0.0, 0.0));
+    body.apply_knockback(Vector2::new(-3.0, 0.0));
+    assert_close(body.velocity.x, -3.0, "velocity straight after the knockback");
+
+    // 3 * (1 - 8 / 60)^n first drops to the max speed of 1 after 8 steps
+    let mut fast_steps = 0;
+    while body.velocity.norm() > 1.0 {
+        body.step(Vector2::new(0.0, 0.0), T_STEP);
+        fast_steps += 1;
+        assert!(fast_steps < 100, "the knockback never wore off");
+    }
+    assert_eq!(fast_steps, 8);
+    assert_close(body.velocity.x, -3.0 * (1.0 - 8.0 * T_STEP).powi(8), "velocity once slowed");
+}
+
+#[test]
+fn input_against_knockback_does_not_raise_the_speed_limit() {
+    let mut body = moving_body(Vector2::new(0.0, 0.0));
+    body.apply_knockback(Vector2::new(3.0, 0.0));
+    body.step(Vector2::new(1.0, 0.0), T_STEP);
+    // Pushing with the knockback may not take the body past the speed it already had
+    assert_close(body.velocity.x, 3.0, "velocity");
+}
+
+#[test]
+fn sliding_along_a_wall_stops_vertical_motion() {
+    let mut body = moving_body(Vector2::new(0.5, 0.4));
+    body.apply_move_report(report(1.0, false, true));
+    assert_eq!(body.velocity, Vector2::new(0.5, 0.0));
+}
+
+#[test]
+fn bouncing_off_a_wall_reverses_vertical_motion() {
+    let mut body = moving_body(Vector2::new(0.5, 0.4));
+    body.wall_response = WallResponse::Bounce(0.5);
+    body.apply_move_report(report(1.0, false, true));
+    assert_close(body.velocity.x, 0.5, "x velocity");
+    assert_close(body.velocity.y, -0.2, "y velocity");
+}
+
+#[test]
+fn move_report_rescales_and_blocks_the_velocity() {
+    let mut body = moving_body(Vector2::new(0.5, 0.4));
+    body.apply_move_report(report(0.5, false, false));
+    assert_close(body.velocity.y, 0.2, "y velocity in the narrower branch");
+    assert_close(body.velocity.x, 0.5, "x velocity");
+
+    body.apply_move_report(report(1.0, true, false));
+    assert_eq!(body.velocity, Vector2::new(0.0, 0.2));
+}
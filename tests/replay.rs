@@ -0,0 +1,131 @@
+// Recording a headless run and playing it back
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, GamePhase};
+use tree_game::tree_game::headless::{HeadlessRunner, ScriptStep};
+use tree_game::tree_game::replay::{Replay, ReplayResult, ReplayError};
+use tree_game::tree_game::action_map::{Action, Binding, Key};
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+
+fn record(phase: GamePhase) -> Replay {
+    let mut game = TreeGame::new(GameSetup::default()).unwrap();
+    game.start_recording();
+    let mut runner = HeadlessRunner::with_phase(game, T_STEP, phase);
+    runner.run_script(&[
+        ScriptStep::moving(20, Vector2::new(1.0, 0.0)),
+        ScriptStep::idle(10),
+        ScriptStep::moving(20, Vector2::new(0.0, 1.0))
+    ]);
+    runner.game.take_recording().expect("the game was recording")
+}
+
+#[test]
+fn replay_remembers_the_phase_it_started_in() {
+    assert_eq!(record(GamePhase::Playing).start_phase, GamePhase::Playing);
+    assert_eq!(record(GamePhase::Title).start_phase, GamePhase::Title);
+}
+
+#[test]
+fn replay_of_a_headless_run_matches() {
+    let replay = record(GamePhase::Playing);
+    let result = replay.play(TreeGame::new(GameSetup::default()).unwrap()).unwrap();
+    assert_eq!(result.frames, 50);
+    assert!(result.matches(), "{}", result.divergence.unwrap());
+}
+
+#[test]
+fn saved_replay_keeps_its_phase_and_frames() {
+    let replay = record(GamePhase::Playing);
+    let loaded = Replay::from_replay_str(&replay.to_replay_string()).expect("the saved replay should load");
+    assert_eq!(loaded.start_phase, GamePhase::Playing);
+    assert_eq!(loaded.frames.len(), replay.frames.len());
+    assert_eq!(loaded.setup, replay.setup);
+    assert_eq!(loaded.tree_fingerprint, replay.tree_fingerprint);
+    assert!(loaded.play(TreeGame::new(GameSetup::default()).unwrap()).unwrap().matches());
+}
+
+#[test]
+fn replays_without_a_phase_start_on_the_title_screen() {
+    let replay = record(GamePhase::Playing);
+    let without_phase: String = replay.to_replay_string().lines().skip(1).map(|line| format!("{}\n", line)).collect();
+    let loaded = Replay::from_replay_str(&without_phase).unwrap();
+    assert_eq!(loaded.start_phase, GamePhase::Title);
+}
+
+#[test]
+fn replay_refuses_a_game_with_a_different_setup() {
+    let replay = record(GamePhase::Playing);
+    let setup = GameSetup {
+        player_max_speed: GameSetup::default().player_max_speed * 2.0,
+        ..GameSetup::default()
+    };
+    assert!(is_mismatch(replay.play(TreeGame::new(setup).unwrap())));
+}
+
+#[test]
+fn replay_refuses_a_game_with_a_different_tree() {
+    let mut replay = record(GamePhase::Playing);
+    replay.setup.clear();
+    let setup = GameSetup {
+        seed: Some(12),
+        ..GameSetup::default()
+    };
+    assert!(is_mismatch(replay.play(TreeGame::new(setup).unwrap())));
+}
+
+#[test]
+fn replays_without_a_header_play_on_any_game() {
+    let mut replay = record(GamePhase::Playing);
+    replay.setup.clear();
+    replay.tree_fingerprint = None;
+    replay.bindings.clear();
+    replay.stick = None;
+    let setup = GameSetup {
+        seed: Some(12),
+        ..GameSetup::default()
+    };
+    assert!(replay.play(TreeGame::new(setup).unwrap()).is_ok());
+}
+
+fn is_mismatch(result: Result<ReplayResult, ReplayError>) -> bool {
+    matches!(result, Err(ReplayError::Mismatch(_)))
+}
+
+#[test]
+fn replay_refuses_a_game_with_different_bindings() {
+    let replay = record(GamePhase::Playing);
+    let mut game = TreeGame::new(GameSetup::default()).unwrap();
+    game.action_map.rebind(Action::MoveRight, Binding::Key(Key::D));
+    assert!(is_mismatch(replay.play(game)));
+
+    let mut game = TreeGame::new(GameSetup::default()).unwrap();
+    game.action_map.clear(Action::MoveUp);
+    assert!(is_mismatch(replay.play(game)));
+}
+
+#[test]
+fn replay_refuses_a_game_with_different_stick_settings() {
+    let replay = record(GamePhase::Playing);
+    let mut game = TreeGame::new(GameSetup::default()).unwrap();
+    game.gamepad_settings.stick.deadzone = 0.35;
+    assert!(is_mismatch(replay.play(game)));
+
+    let mut game = TreeGame::new(GameSetup::default()).unwrap();
+    game.gamepad_settings.stick.response_exponent = 1.0;
+    assert!(is_mismatch(replay.play(game)));
+}
+
+#[test]
+fn saved_replay_keeps_its_input_settings() {
+    let replay = record(GamePhase::Playing);
+    let loaded = Replay::from_replay_str(&replay.to_replay_string()).unwrap();
+    assert!(!loaded.bindings.is_empty());
+    assert_eq!(loaded.bindings, replay.bindings);
+    let (stick, loaded_stick) = (replay.stick.unwrap(), loaded.stick.unwrap());
+    assert_eq!(loaded_stick.deadzone, stick.deadzone);
+    assert_eq!(loaded_stick.response_exponent, stick.response_exponent);
+    assert!(loaded.play(TreeGame::new(GameSetup::default()).unwrap()).unwrap().matches());
+}
@@ -0,0 +1,121 @@
+// Points for pickups: their kind, the layer they sit on and combos of quick collections
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup};
+use tree_game::tree_game::headless::HeadlessRunner;
+use tree_game::tree_game::pickup::{Pickup, ScoreRules};
+use tree_game::tree_game::movable::Movable;
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::tree::{BranchId, PickupKind};
+use na::Vector2;
+
+const T_STEP: f64 = 1.0 / 60.0;
+
+fn pickup_on_layer(kind: PickupKind, layer: usize) -> Pickup {
+    Pickup::new(Position::new(BranchId::new(0, layer), Vector2::new(0.1, 0.0)), kind)
+}
+
+#[test]
+fn points_depend_on_the_kind_of_pickup() {
+    let rules = ScoreRules::default();
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Fruit, 0), 1), 100);
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Leaf, 0), 1), 25);
+}
+
+#[test]
+fn pickups_further_out_are_worth_more() {
+    let rules = ScoreRules::default();
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Fruit, 1), 1), 200);
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Fruit, 3), 1), 400);
+
+    let rules = ScoreRules { layer_bonus: 0.5, ..ScoreRules::default() };
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Leaf, 2), 1), 50);
+    assert_eq!(rules.get_points(&pickup_on_layer(PickupKind::Leaf, 3), 2), 125);
+}
+
+#[test]
+fn combos_multiply_points_up_to_the_cap() {
+    let rules = ScoreRules::default();
+    let fruit = pickup_on_layer(PickupKind::Fruit, 1);
+    assert_eq!(rules.get_points(&fruit, 0), 200, "a pickup always counts at least once");
+    assert_eq!(rules.get_points(&fruit, 3), 600);
+    assert_eq!(rules.get_points(&fruit, 5), 1000);
+    assert_eq!(rules.get_points(&fruit, 9), 1000);
+}
+
+// A game with nothing on the tree but the player, who stays where they started
+fn new_runner() -> HeadlessRunner {
+    let mut runner = HeadlessRunner::new(TreeGame::new(GameSetup::default()).unwrap(), T_STEP);
+    runner.game.pickups.clear();
+    runner.game.enemies.clear();
+    runner
+}
+
+// Puts a pickup under the player, then runs the frame that collects it
+fn collect(runner: &mut HeadlessRunner, kind: PickupKind) {
+    let position = runner.game.player.get_position();
+    runner.game.pickups.push(Pickup::new(position, kind));
+    runner.step(&Default::default());
+    assert!(runner.game.pickups.is_empty(), "the pickup was not collected");
+}
+
+fn wait(runner: &mut HeadlessRunner, seconds: f64) {
+    for _ in 0..(seconds / T_STEP).round() as usize {
+        runner.step(&Default::default());
+    }
+}
+
+#[test]
+fn collecting_a_pickup_adds_its_points() {
+    let mut runner = new_runner();
+    assert_eq!(runner.game.player.get_position().get_branch_id().layer, 0);
+
+    collect(&mut runner, PickupKind::Fruit);
+
+    assert_eq!(runner.game.state.score, 100);
+    assert_eq!(runner.game.state.combo, 1);
+    assert_eq!(runner.game.state.combo_time_left, ScoreRules::default().combo_window);
+}
+
+#[test]
+fn pickups_inside_the_combo_window_extend_the_combo() {
+    let mut runner = new_runner();
+
+    collect(&mut runner, PickupKind::Fruit);
+    wait(&mut runner, 1.0);
+    collect(&mut runner, PickupKind::Leaf);
+    wait(&mut runner, 1.5);
+    collect(&mut runner, PickupKind::Fruit);
+
+    assert_eq!(runner.game.state.combo, 3);
+    assert_eq!(runner.game.state.score, 100 + 25 * 2 + 100 * 3);
+}
+
+#[test]
+fn combos_end_when_the_window_runs_out() {
+    let mut runner = new_runner();
+    collect(&mut runner, PickupKind::Fruit);
+    collect(&mut runner, PickupKind::Fruit);
+    assert_eq!(runner.game.state.combo, 2);
+
+    wait(&mut runner, 2.5);
+    assert_eq!(runner.game.state.combo, 0);
+    collect(&mut runner, PickupKind::Fruit);
+
+    assert_eq!(runner.game.state.combo, 1);
+    assert_eq!(runner.game.state.score, 100 + 200 + 100);
+}
+
+#[test]
+fn combos_stop_growing_at_the_cap() {
+    let mut runner = new_runner();
+    runner.game.score_rules.max_combo = 2;
+
+    for _ in 0..4 {
+        collect(&mut runner, PickupKind::Leaf);
+    }
+
+    assert_eq!(runner.game.state.combo, 2);
+    assert_eq!(runner.game.state.score, 25 + 50 + 50 + 50);
+}
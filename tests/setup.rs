@@ -0,0 +1,141 @@
+// Reading and writing game setup files, and the settings they are allowed to hold
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, Difficulty, SetupError};
+use tree_game::tree_game::setup::MAX_LAYERS;
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::tree::BranchId;
+use na::Vector2;
+use std::env;
+use std::fs;
+
+fn custom_setup() -> GameSetup {
+    GameSetup {
+        num_layers: 6,
+        vertical_fill: 0.35,
+        horizontal_fill: 0.45,
+        seed: Some(42),
+        player_start: Position::new(BranchId::new(0, 0), Vector2::new(0.125, -0.05)),
+        player_length: 0.25,
+        player_height: 0.075,
+        player_acceleration: 12.5,
+        player_max_speed: 1.5,
+        player_friction: 0.0,
+        enemy_count: 7,
+        difficulty: Difficulty::Hard
+    }
+}
+
+fn assert_same_setup(actual: &GameSetup, expected: &GameSetup) {
+    assert_eq!(actual.to_config_string(), expected.to_config_string());
+    assert_eq!(actual.num_layers, expected.num_layers);
+    assert_eq!(actual.seed, expected.seed);
+    assert_eq!(actual.player_start.get_branch_id(), expected.player_start.get_branch_id());
+    assert_eq!(actual.player_start.get_branch_position(), expected.player_start.get_branch_position());
+    assert_eq!(actual.difficulty, expected.difficulty);
+}
+
+fn assert_invalid(config: &str) {
+    match GameSetup::from_config_str(config) {
+        Err(SetupError::Invalid(_)) => (),
+        other => panic!("expected {:?} to be rejected, got {:?}", config, other)
+    }
+}
+
+fn assert_parse_error(config: &str, expected_line: usize) {
+    match GameSetup::from_config_str(config) {
+        Err(SetupError::Parse { line, .. }) => assert_eq!(line, expected_line, "{:?}", config),
+        other => panic!("expected a parse error for {:?}, got {:?}", config, other)
+    }
+}
+
+#[test]
+fn setups_round_trip_through_the_config_format() {
+    let setup = custom_setup();
+    assert_same_setup(&GameSetup::from_config_str(&setup.to_config_string()).unwrap(), &setup);
+
+    let setup = GameSetup::default();
+    assert_same_setup(&GameSetup::from_config_str(&setup.to_config_string()).unwrap(), &setup);
+}
+
+#[test]
+fn setups_round_trip_through_a_file() {
+    let path = env::temp_dir().join(format!("tree_game_setup_{}.cfg", std::process::id()));
+    let setup = custom_setup();
+
+    setup.save(&path).unwrap();
+    let loaded = GameSetup::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_same_setup(&loaded, &setup);
+}
+
+#[test]
+fn settings_left_out_keep_their_defaults() {
+    let setup = GameSetup::from_config_str("# a short setup\nlayers 5\n\ndifficulty easy  # for testing\n").unwrap();
+
+    let expected = GameSetup {
+        num_layers: 5,
+        difficulty: Difficulty::Easy,
+        ..GameSetup::default()
+    };
+    assert_same_setup(&setup, &expected);
+}
+
+#[test]
+fn layer_counts_must_be_within_range() {
+    assert_invalid("layers 0");
+    assert_invalid(&format!("layers {}", MAX_LAYERS + 1));
+    assert!(GameSetup::from_config_str(&format!("layers {}", MAX_LAYERS)).is_ok());
+}
+
+#[test]
+fn vertical_fill_must_be_a_fraction_of_the_height() {
+    assert_invalid("vertical_fill 0");
+    assert_invalid("vertical_fill -0.5");
+    assert_invalid("vertical_fill 1.01");
+    assert_invalid("vertical_fill NaN");
+    assert!(GameSetup::from_config_str("vertical_fill 1").is_ok());
+}
+
+#[test]
+fn horizontal_fill_must_leave_room_for_later_layers() {
+    assert_invalid("horizontal_fill 0");
+    assert_invalid("horizontal_fill 1");
+    assert_invalid("horizontal_fill inf");
+    assert!(GameSetup::from_config_str("horizontal_fill 0.99").is_ok());
+}
+
+#[test]
+fn player_sizes_and_speeds_must_be_positive() {
+    assert_invalid("player_size 0 0.1");
+    assert_invalid("player_size 0.5 -0.1");
+    assert_invalid("player_acceleration 0");
+    assert_invalid("player_max_speed -1");
+}
+
+#[test]
+fn player_friction_must_not_be_negative() {
+    assert_invalid("player_friction -0.5");
+    assert!(GameSetup::from_config_str("player_friction 0").is_ok());
+}
+
+#[test]
+fn unreadable_lines_are_reported_with_their_line() {
+    assert_parse_error("layers 4\nbranches 9\n", 2);
+    assert_parse_error("seed\n", 1);
+    assert_parse_error("layers 4\n\nplayer_start 0 0 0.25\n", 3);
+    assert_parse_error("layers four\n", 1);
+    assert_parse_error("difficulty impossible\n", 1);
+}
+
+#[test]
+fn starts_are_checked_against_the_tree_built() {
+    // Nothing about the start can be checked before there is a tree to start on
+    let setup = GameSetup::from_config_str("layers 2\nplayer_start 0 5 0.1 0\n").unwrap();
+    match TreeGame::new(setup) {
+        Err(SetupError::Invalid(message)) => assert!(message.contains("not in the tree"), "{}", message),
+        other => panic!("expected the start to be rejected, got {:?}", other.err())
+    }
+}
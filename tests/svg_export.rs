@@ -0,0 +1,109 @@
+// Exporting trees as SVG images, with and without the debugging overlays
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::svg_export::{SvgExport, SvgOptions};
+use tree_game::tree_game::position::Position;
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBuilder, BranchId};
+use na::{Vector2, Vector4};
+
+fn build_tree() -> TreeData {
+    TreeBuilder::new(3).build_tree()
+}
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+fn export(tree: &TreeData, options: SvgOptions) -> String {
+    let mut export = SvgExport::new(tree, options);
+    // Over the end of the root, so it covers a branch on either side of the join
+    let root_length = tree.get_root().get_logical().length;
+    export.add_entity(Position::new(BranchId::new(0, 0), Vector2::new(root_length - 0.05, 0.0)), 0.3, 0.1, Vector4::new(1.0, 0.0, 0.0, 1.0));
+    // Not on the tree, so left out
+    export.add_entity(Position::new(BranchId::new(99, 9), Vector2::new(0.1, 0.0)), 0.3, 0.1, Vector4::new(1.0, 0.0, 0.0, 1.0));
+    export.to_svg_string()
+}
+
+fn all_overlays() -> SvgOptions {
+    SvgOptions {
+        show_labels: true,
+        show_connections: true,
+        show_entities: true,
+        ..Default::default()
+    }
+}
+
+fn no_overlays() -> SvgOptions {
+    SvgOptions {
+        show_labels: false,
+        show_connections: false,
+        show_entities: false,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn each_branch_is_drawn_as_one_path() {
+    let tree = build_tree();
+    let svg = export(&tree, no_overlays());
+    assert_eq!(count(&svg, "<path "), tree.get_branches().len());
+}
+
+#[test]
+fn overlays_are_left_out_unless_asked_for() {
+    let svg = export(&build_tree(), no_overlays());
+    assert_eq!(count(&svg, "<line "), 0);
+    assert_eq!(count(&svg, "<text "), 0);
+    assert_eq!(count(&svg, "<polygon "), 0);
+}
+
+#[test]
+fn connection_overlays_draw_every_connection() {
+    let tree = build_tree();
+    let connections: usize = tree.get_branches().values().map(|branch| branch.get_connections().len()).sum();
+    let svg = export(&tree, SvgOptions { show_connections: true, ..no_overlays() });
+    assert_eq!(count(&svg, "<line "), connections);
+    assert_eq!(count(&svg, "<text "), 0);
+}
+
+#[test]
+fn labels_name_every_branch() {
+    let tree = build_tree();
+    let svg = export(&tree, SvgOptions { show_labels: true, ..no_overlays() });
+    assert_eq!(count(&svg, "<text "), tree.get_branches().len());
+    for id in tree.get_branches().keys() {
+        assert!(svg.contains(&format!(">{},{}</text>", id.id, id.layer)), "no label for {:?}", id);
+    }
+    assert_eq!(count(&svg, "<line "), 0);
+}
+
+#[test]
+fn entities_are_drawn_on_every_branch_they_cover() {
+    let svg = export(&build_tree(), SvgOptions { show_entities: true, ..no_overlays() });
+    assert_eq!(count(&svg, "<polygon "), 2);
+    assert_eq!(count(&svg, "fill=\"#ff0000\""), 2);
+}
+
+#[test]
+fn output_is_well_formed() {
+    let svg = export(&build_tree(), all_overlays());
+    let lines: Vec<&str> = svg.lines().collect();
+
+    assert!(lines[0].starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\""));
+    assert_eq!(lines[lines.len() - 1], "</svg>");
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(count(&svg, "<svg"), 1);
+    for line in &lines[1..lines.len() - 1] {
+        let closed = line.ends_with("/>") || (line.starts_with("  <text ") && line.ends_with("</text>"));
+        assert!(line.starts_with("  <") && closed, "unexpected line {:?}", line);
+        assert_eq!(count(line, "\"") % 2, 0, "unbalanced quotes in {:?}", line);
+        assert!(!line.contains("NaN") && !line.contains("inf"), "bad number in {:?}", line);
+    }
+}
+
+#[test]
+fn the_same_tree_gives_the_same_file() {
+    let tree = build_tree();
+    assert_eq!(export(&tree, all_overlays()), export(&tree, all_overlays()));
+}
@@ -0,0 +1,131 @@
+// Switching colour themes while the game runs, and saving and loading them
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::{TreeGame, GameSetup, Theme, ThemeError, BUILT_IN_THEMES};
+use tree_game::tree_game::theme::{BranchColoring, BranchInfo};
+use tree_game::tree_game::headless::{HeadlessRunner, ScriptStep};
+use tree_game::tree_game::tree::BranchId;
+use na::Vector4;
+use std::env;
+use std::fs;
+use std::rc::Rc;
+
+fn new_game() -> TreeGame {
+    TreeGame::new(GameSetup::default()).unwrap()
+}
+
+#[test]
+fn next_theme_cycles_through_the_built_in_themes() {
+    let mut game = new_game();
+    assert_eq!(game.theme.name, BUILT_IN_THEMES[0]);
+    for &name in BUILT_IN_THEMES.iter().skip(1).chain(BUILT_IN_THEMES.iter().take(1)) {
+        game.next_theme();
+        assert_eq!(game.theme.name, name);
+    }
+}
+
+#[test]
+fn next_theme_carries_on_from_a_chosen_theme() {
+    let mut game = new_game();
+    game.set_theme(Theme::built_in(BUILT_IN_THEMES[2]).unwrap());
+    game.next_theme();
+    assert_eq!(game.theme.name, BUILT_IN_THEMES[3]);
+}
+
+#[test]
+fn theme_file_joins_the_cycle_and_is_read_again() {
+    let path = env::temp_dir().join(format!("tree_game_theme_{}.theme", std::process::id()));
+    let mut custom = Theme::bark();
+    custom.name = "custom".to_string();
+    custom.save(&path).unwrap();
+
+    let mut game = new_game();
+    game.set_theme_file(&path).unwrap();
+    assert_eq!(game.theme.name, "custom");
+    for _ in 0..BUILT_IN_THEMES.len() {
+        game.next_theme();
+    }
+    assert_eq!(game.theme.name, BUILT_IN_THEMES[BUILT_IN_THEMES.len() - 1]);
+
+    custom.name = "edited".to_string();
+    custom.save(&path).unwrap();
+    game.next_theme();
+    assert_eq!(game.theme.name, "edited");
+
+    // A file that can no longer be read is skipped
+    fs::remove_file(&path).unwrap();
+    for _ in 0..BUILT_IN_THEMES.len() + 1 {
+        game.next_theme();
+    }
+    assert_eq!(game.theme.name, BUILT_IN_THEMES[0]);
+}
+
+#[test]
+fn holding_the_key_changes_the_theme_once() {
+    let mut runner = HeadlessRunner::new(new_game(), 1.0 / 60.0);
+    let mut script = ScriptStep::idle(10);
+    script.kbd.p = true;
+    runner.run_script(&[script.clone(), ScriptStep::idle(1), script]);
+    assert_eq!(runner.game.theme.name, BUILT_IN_THEMES[2]);
+}
+
+#[test]
+fn built_in_themes_round_trip_through_the_theme_format() {
+    let info = BranchInfo { id: BranchId::new(0, 2), is_trunk: false, depth: 0.5 };
+    let level_color = Vector4::new(0.1, 0.1, 1.0, 1.0);
+    for &name in BUILT_IN_THEMES.iter() {
+        let theme = Theme::built_in(name).unwrap();
+        let contents = theme.to_theme_string().unwrap();
+
+        let loaded = Theme::from_theme_str(&contents).unwrap();
+
+        assert_eq!(loaded.to_theme_string().unwrap(), contents);
+        assert_eq!(loaded.name, name);
+        assert_eq!(loaded.enemy, theme.enemy);
+        assert_eq!(loaded.branches.get_color(&info, level_color), theme.branches.get_color(&info, level_color), "{}", name);
+    }
+}
+
+#[test]
+fn custom_branch_rules_are_not_saved_as_something_else() {
+    let path = env::temp_dir().join(format!("tree_game_custom_theme_{}.theme", std::process::id()));
+    let mut theme = Theme::classic();
+    theme.name = "striped".to_string();
+    theme.branches = BranchColoring::Custom(Rc::new(|info: &BranchInfo| {
+        if info.is_trunk { Vector4::new(1.0, 1.0, 1.0, 1.0) } else { Vector4::new(0.0, 0.0, 0.0, 1.0) }
+    }));
+
+    match theme.to_theme_string() {
+        Err(ThemeError::CustomRule(name)) => assert_eq!(name, "striped"),
+        other => panic!("expected the custom rule to be refused, got {:?}", other)
+    }
+    assert!(theme.save(&path).is_err());
+    assert!(!path.exists(), "nothing should be written for a theme that can't be saved");
+}
+
+#[test]
+fn short_colours_are_reported_with_their_line() {
+    match Theme::from_theme_str("name broken\nbranches gradient 0 0 0 1 1 1 1\n") {
+        Err(ThemeError::Parse { line, .. }) => assert_eq!(line, 2),
+        other => panic!("expected a parse error, got {:?}", other.err())
+    }
+}
+
+fn luminance(color: Vector4<f64>) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+#[test]
+fn high_contrast_entities_are_brighter_than_every_branch() {
+    let theme = Theme::high_contrast();
+    let brightest_branch = (0..=10)
+        .map(|step| BranchInfo { id: BranchId::new(0, 0), is_trunk: true, depth: step as f64 / 10.0 })
+        .map(|info| luminance(theme.branches.get_color(&info, Vector4::new(0.0, 0.0, 0.0, 1.0))))
+        .fold(0.0, f64::max);
+
+    for &(what, color) in [("player", theme.player), ("enemy", theme.enemy), ("fruit", theme.fruit), ("leaf", theme.leaf)].iter() {
+        assert!(luminance(color) - brightest_branch > 0.25, "the {} is too dark against the branches", what);
+    }
+    assert!(luminance(theme.enemy) > 0.8, "enemies should be among the brightest things on screen");
+}
@@ -0,0 +1,82 @@
+// Building trees from seeds and split settings
+extern crate tree_game;
+
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBuilder, RandomSpec, Distribution, Boundary};
+
+#[test]
+fn same_seed_builds_the_same_tree() {
+    let first = TreeBuilder::new_random(5, RandomSpec::new(42)).with_pickups(0.2, 3).with_enemies(3, 4).build_tree();
+    let second = TreeBuilder::new_random(5, RandomSpec::new(42)).with_pickups(0.2, 3).with_enemies(3, 4).build_tree();
+    assert_eq!(first.to_level_string(), second.to_level_string());
+}
+
+#[test]
+fn different_seeds_build_different_trees() {
+    let first = TreeBuilder::new_random(5, RandomSpec::new(42)).build_tree();
+    let second = TreeBuilder::new_random(5, RandomSpec::new(43)).build_tree();
+    assert_ne!(first.to_level_string(), second.to_level_string());
+}
+
+// Every branch's left end lies exactly on the part of its parent's right end it is connected to
+fn assert_no_seams(tree: &TreeData) {
+    for (&id, branch) in tree.get_branches() {
+        let parent_id = match tree.get_parent(id).unwrap() {
+            Some(parent_id) => parent_id,
+            None => continue
+        };
+        let parent = tree.get_branch(parent_id);
+        let connection = parent.get_connections().iter().find(|c| c.get_branch_id() == id).unwrap();
+        let (bottom, top) = match connection.get_boundary() {
+            Boundary::Right(bottom, top) => (bottom, top),
+            Boundary::Left(..) => panic!("{:?} joins the left of its parent", id)
+        };
+        let parent_visual = parent.get_visual();
+        let visual = branch.get_visual();
+        for &(parent_v, v) in [(bottom, -1.0), (top, 1.0)].iter() {
+            let gap = (parent_visual.get_point(1.0, parent_v) - visual.get_point(0.0, v)).norm();
+            assert!(gap < 1e-9, "{:?} is {} away from {:?}", id, gap, parent_id);
+        }
+    }
+}
+
+#[test]
+fn three_way_splits_meet_without_seams() {
+    let tree = TreeBuilder::new(4).with_split_count(3).build_tree();
+    // A trunk and its three branches for each of 1, 3, 9 and 27 trunks
+    assert_eq!(tree.get_branches().len(), 4 * 40);
+    assert_no_seams(&tree);
+    assert!(tree.validate().is_empty());
+}
+
+#[test]
+fn mixed_split_counts_meet_without_seams() {
+    let tree = TreeBuilder::new(4).with_split_counts(|id| 1 + (id.id + id.layer) % 4).build_tree();
+    assert_no_seams(&tree);
+    assert!(tree.validate().is_empty());
+}
+
+#[test]
+fn weighted_splits_meet_without_seams() {
+    for seed in 0..10 {
+        let spec = RandomSpec {
+            split_count: Distribution::Uniform(1.0, 4.0),
+            ..RandomSpec::new(seed)
+        };
+        let tree = TreeBuilder::new_random(5, spec).build_tree();
+        assert_no_seams(&tree);
+        assert!(tree.validate().is_empty(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn other_fills_meet_without_seams() {
+    for &(vertical_fill, horizontal_fill) in [(0.1, 0.2), (0.3, 0.5), (0.5, 0.9)].iter() {
+        let tree = TreeBuilder::new(4).with_split_count(3).with_fills(vertical_fill, horizontal_fill).build_tree();
+        assert_no_seams(&tree);
+        assert!(tree.validate().is_empty());
+        for branch in tree.get_branches().values() {
+            let width = branch.get_visual().patch.width;
+            assert!(width.is_finite() && width > 0.0, "width {} for fills {} {}", width, vertical_fill, horizontal_fill);
+        }
+    }
+}
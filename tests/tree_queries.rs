@@ -0,0 +1,96 @@
+// Walking the parent and child links of a built tree
+extern crate tree_game;
+
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBranch, TreeBuilder, TreeError, BranchId};
+
+#[test]
+fn unknown_branches_are_errors() {
+    let tree = TreeBuilder::new(2).build_tree();
+    let missing = BranchId::new(99, 99);
+    assert!(match tree.get_parent(missing) { Err(TreeError::MissingBranch(id)) => id == missing, _ => false });
+    assert!(tree.get_children(missing).is_err());
+    assert!(tree.get_siblings(missing).is_err());
+    assert!(tree.get_ancestors(missing).is_err());
+    assert!(tree.get_depth(missing).is_err());
+}
+
+#[test]
+fn every_branch_leads_back_to_the_root() {
+    let tree = TreeBuilder::new(3).build_tree();
+    let root = BranchId::new(0, 0);
+    assert_eq!(tree.get_parent(root).unwrap(), None);
+    for &id in tree.get_branches().keys() {
+        let ancestors = tree.get_ancestors(id).unwrap();
+        if id != root {
+            assert_eq!(ancestors.last(), Some(&root), "ancestors of {:?}", id);
+        }
+        assert_eq!(tree.get_depth(id).unwrap(), ancestors.len());
+        for child in tree.get_children(id).unwrap() {
+            assert_eq!(tree.get_parent(child).unwrap(), Some(id));
+        }
+    }
+}
+
+#[test]
+fn enemies_are_placed_on_different_branches_away_from_the_root() {
+    let tree = TreeBuilder::new(4).with_enemies(5, 9).build_tree();
+    let enemies = tree.get_enemies();
+    assert_eq!(enemies.len(), 5);
+    for (index, enemy) in enemies.iter().enumerate() {
+        let id = enemy.position.get_branch_id();
+        assert!(id.layer > 0);
+        assert!(tree.try_get_branch(id).is_ok());
+        assert!(enemies[..index].iter().all(|other| other.position.get_branch_id() != id));
+    }
+}
+
+#[test]
+fn path_runs_through_the_common_ancestor() {
+    let tree = TreeBuilder::new(2).build_tree();
+    let path = tree.get_path(BranchId::new(1, 1), BranchId::new(4, 1)).unwrap().unwrap();
+    let expected: Vec<BranchId> = [(1, 1), (0, 1), (1, 0), (0, 0), (2, 0), (3, 1), (4, 1)]
+        .iter()
+        .map(|&(id, layer)| BranchId::new(id, layer))
+        .collect();
+    assert_eq!(path, expected);
+    assert_eq!(tree.get_path(BranchId::new(2, 0), BranchId::new(2, 0)).unwrap(), Some(vec![BranchId::new(2, 0)]));
+}
+
+#[test]
+fn path_distance_counts_half_of_each_end() {
+    let tree = TreeBuilder::new(2).build_tree();
+    let length = |id: BranchId| tree.get_branch(id).get_logical().length;
+    let (root, top) = (BranchId::new(0, 0), BranchId::new(1, 0));
+    let distance = tree.get_path_distance(root, BranchId::new(0, 1)).unwrap().unwrap();
+    assert!((distance - (length(root) / 2.0 + length(top) + length(BranchId::new(0, 1)) / 2.0)).abs() < 1e-9);
+    assert_eq!(tree.get_path_distance(top, top).unwrap(), Some(0.0));
+}
+
+#[test]
+fn unconnected_branches_have_no_path() {
+    let tree = TreeBuilder::new(2).build_tree();
+    let island = BranchId::new(1, 1);
+    // Cut both sides of the link between 1 1 and its parent
+    let mut branches = tree.get_branches().clone();
+    for (&id, branch) in tree.get_branches() {
+        let connections = branch.get_connections()
+            .iter()
+            .filter(|c| id != island && c.get_branch_id() != island)
+            .cloned()
+            .collect();
+        branches.insert(id, TreeBranch::from_parts(id, branch.get_visual(), branch.get_logical(), connections));
+    }
+    let tree = TreeData::new(branches, 1);
+    assert_eq!(tree.get_path(BranchId::new(0, 0), island).unwrap(), None);
+    assert_eq!(tree.get_path_distance(BranchId::new(0, 0), island).unwrap(), None);
+}
+
+#[test]
+fn paths_to_unknown_branches_are_errors() {
+    let tree = TreeBuilder::new(2).build_tree();
+    let missing = BranchId::new(99, 99);
+    for &(start, end) in [(missing, BranchId::new(0, 0)), (BranchId::new(0, 0), missing)].iter() {
+        assert!(match tree.get_path(start, end) { Err(TreeError::MissingBranch(id)) => id == missing, _ => false });
+        assert!(match tree.get_path_distance(start, end) { Err(TreeError::MissingBranch(id)) => id == missing, _ => false });
+    }
+}
@@ -0,0 +1,130 @@
+// Structural checks on trees, one broken tree for each kind of error
+extern crate tree_game;
+extern crate nalgebra as na;
+
+use tree_game::tree_game::tree::{Tree, TreeData, TreeBranch, TreeBuilder, BranchId, BranchSide, Boundary, Connection,
+    PickupSpawn, PickupKind, EnemySpawn, EnemyBehaviour, ValidationError};
+use tree_game::tree_game::position::Position;
+use na::Vector2;
+use std::collections::HashMap;
+
+fn build_tree() -> TreeData {
+    TreeBuilder::new(2).build_tree()
+}
+
+fn get_branches(tree: &TreeData) -> HashMap<BranchId, TreeBranch> {
+    tree.get_branches().clone()
+}
+
+// The tree with the connections of one branch swapped out
+fn with_connections(tree: &TreeData, id: BranchId, connections: Vec<Connection>) -> TreeData {
+    let mut branches = get_branches(tree);
+    let branch = tree.get_branch(id);
+    branches.insert(id, TreeBranch::from_parts(id, branch.get_visual(), branch.get_logical(), connections));
+    TreeData::new(branches, 1)
+}
+
+// The root's connections to its two branches, with the top one covering the given part of the right side
+fn with_root_top(start: f64, end: f64) -> TreeData {
+    with_connections(&build_tree(), BranchId::new(0, 0), vec![
+        Connection::new(BranchId::new(1, 0), Boundary::Right(start, end)),
+        Connection::new(BranchId::new(2, 0), Boundary::Right(-1.0, 0.0))
+    ])
+}
+
+#[test]
+fn built_trees_are_valid() {
+    assert!(build_tree().validate().is_empty());
+}
+
+#[test]
+fn missing_root_is_reported() {
+    let mut branches = get_branches(&build_tree());
+    branches.remove(&BranchId::new(0, 0));
+    let errors = TreeData::new(branches, 1).validate();
+    assert!(errors.iter().any(|error| matches!(*error, ValidationError::MissingRoot)));
+}
+
+#[test]
+fn dangling_connection_is_reported() {
+    let id = BranchId::new(1, 1);
+    let tree = with_connections(&build_tree(), id, vec![
+        Connection::new(BranchId::new(0, 1), Boundary::Left(-1.0, 1.0)),
+        Connection::new(BranchId::new(9, 9), Boundary::Right(-1.0, 1.0))
+    ]);
+    let errors = tree.validate();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(match errors[0] {
+        ValidationError::DanglingConnection { branch, target } => branch == id && target == BranchId::new(9, 9),
+        _ => false
+    });
+}
+
+#[test]
+fn one_sided_connection_is_reported() {
+    // 1 1 forgets its parent, which still points at it
+    let tree = with_connections(&build_tree(), BranchId::new(1, 1), Vec::new());
+    let errors = tree.validate();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(match errors[0] {
+        ValidationError::OneSidedConnection { branch, target } => branch == BranchId::new(0, 1) && target == BranchId::new(1, 1),
+        _ => false
+    });
+}
+
+#[test]
+fn overlapping_boundaries_are_reported() {
+    let errors = with_root_top(-0.5, 1.0).validate();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(match errors[0] {
+        ValidationError::OverlappingBoundaries { branch, .. } => branch == BranchId::new(0, 0),
+        _ => false
+    });
+}
+
+#[test]
+fn boundary_gap_is_reported() {
+    let errors = with_root_top(0.5, 1.0).validate();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(match errors[0] {
+        ValidationError::BoundaryGap { branch, side, start, end } =>
+            branch == BranchId::new(0, 0) && side == BranchSide::Right && start == 0.0 && end == 0.5,
+        _ => false
+    });
+}
+
+#[test]
+fn boundary_out_of_range_is_reported() {
+    for &(start, end) in [(0.0, 1.5), (-2.0, 1.0), (1.0, 0.0)].iter() {
+        let errors = with_root_top(start, end).validate();
+        let out_of_range = |error: &ValidationError| matches!(
+            *error,
+            ValidationError::BoundaryOutOfRange { branch, .. } if branch == BranchId::new(0, 0)
+        );
+        assert!(errors.iter().any(out_of_range), "{} {}: {:?}", start, end, errors);
+    }
+}
+
+#[test]
+fn non_finite_boundaries_are_reported_without_panicking() {
+    for &(start, end) in [(f64::NAN, 1.0), (0.0, f64::NAN), (f64::NEG_INFINITY, 1.0)].iter() {
+        let errors = with_root_top(start, end).validate();
+        let out_of_range = |error: &ValidationError| matches!(
+            *error,
+            ValidationError::BoundaryOutOfRange { branch, .. } if branch == BranchId::new(0, 0)
+        );
+        assert!(errors.iter().any(out_of_range), "{} {}: {:?}", start, end, errors);
+    }
+}
+
+#[test]
+fn spawns_on_missing_branches_are_reported() {
+    let missing = BranchId::new(7, 1);
+    let mut tree = build_tree();
+    tree.add_pickup(PickupSpawn::new(PickupKind::Leaf, Position::new(missing, Vector2::new(0.1, 0.0))));
+    tree.add_enemy(EnemySpawn::new(EnemyBehaviour::Patrol, Position::new(missing, Vector2::new(0.1, 0.0))));
+    let errors = tree.validate();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(match errors[0] { ValidationError::DanglingPickup { branch } => branch == missing, _ => false });
+    assert!(match errors[1] { ValidationError::DanglingEnemy { branch } => branch == missing, _ => false });
+}